@@ -0,0 +1,44 @@
+//! Compares building a deck by collecting every card first against sampling
+//! it while the cards are generated, and against only counting them.
+//!
+//! Run with `cargo bench`.
+
+use std::time::Instant;
+
+use musical_flash_cards::{CardGenerator, Schedule};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const RUNS: u32 = 5;
+
+fn time(name: &str, mut run: impl FnMut(&mut ChaCha8Rng) -> usize) {
+    let start = Instant::now();
+    let mut cards = 0;
+    for seed in 0..RUNS {
+        cards = run(&mut ChaCha8Rng::seed_from_u64(seed.into()));
+    }
+    println!(
+        "{:<28} {:>12.2?} per deck, {} cards",
+        name,
+        start.elapsed() / RUNS,
+        cards
+    );
+}
+
+fn main() {
+    let schedule = Schedule::default();
+    for (name, settings) in [
+        ("no sharps or flats", CardGenerator::no_sharps_flats()),
+        ("tenor clef advanced", CardGenerator::tenor_clef_advanced()),
+        ("impossible", CardGenerator::impossible()),
+    ] {
+        println!("{}", name);
+        time("  collected", |rng| {
+            settings.collected_card_generator(rng, &schedule).len()
+        });
+        time("  sampled", |rng| {
+            settings.card_generator(rng, &schedule).len()
+        });
+        time("  counted", |rng| settings.card_count(rng));
+    }
+}
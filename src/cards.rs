@@ -1,1543 +1,4376 @@
-use rand::prelude::SliceRandom;
-use rand::{thread_rng, Rng};
-use std::cell::RefCell;
-use std::num::NonZeroUsize;
-use wasm_bindgen::prelude::*;
-use web_sys::{Element, HtmlInputElement, SvgsvgElement};
-
-use crate::SVG_NAMESPACE;
-
-#[derive(Debug)]
-pub struct Card {
-    clef: Clef,
-    notes: [Note; 3],
-}
-
-impl FromStr for Card {
-    type Err = ();
-    fn from_str(src: &str) -> Result<Self, Self::Err> {
-        let mut elements = src.split(':');
-        macro_rules! parse_next {
-            () => {
-                elements.next().and_then(|s| s.parse().ok()).ok_or(())?
-            };
-        }
-        Ok(Card {
-            clef: parse_next!(),
-            notes: [parse_next!(), parse_next!(), parse_next!()],
-        })
-    }
-}
-
-pub fn status_card(source: &str) -> Element {
-    let document = web_sys::window().unwrap().document().unwrap();
-    let card: Element = document.create_element("div").unwrap();
-    card.set_attribute("width", "100%").unwrap();
-    card.set_attribute("height", "100%").unwrap();
-    card.set_attribute("style", "font-size: 24px; text-align: center;")
-        .unwrap();
-    card.set_inner_html(source);
-    card
-}
-
-impl From<&Card> for Element {
-    fn from(card: &Card) -> Self {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let card_svg: SvgsvgElement = document
-            .create_element_ns(SVG_NAMESPACE, "svg")
-            .unwrap()
-            .dyn_into()
-            .unwrap();
-        card_svg.set_attribute("viewBox", "0 0 300 200").unwrap();
-        card_svg.set_attribute("width", "100%").unwrap();
-        card_svg.set_attribute("height", "100%").unwrap();
-
-        let mut current_height = 80;
-        for _ in 0..5 {
-            let y = &current_height.to_string();
-            let line = document.create_element_ns(SVG_NAMESPACE, "line").unwrap();
-            line.set_attribute("x1", "0").unwrap();
-            line.set_attribute("x2", "300").unwrap();
-            line.set_attribute("y1", y).unwrap();
-            line.set_attribute("y2", y).unwrap();
-            line.set_attribute("style", "stroke: black; stroke-width: 1;")
-                .unwrap();
-            card_svg.append_child(&line).unwrap();
-            current_height += 10;
-        }
-
-        let clef = document.create_element_ns(SVG_NAMESPACE, "image").unwrap();
-        match card.clef {
-            Clef::Treble => {
-                clef.set_attribute("x", "10").unwrap();
-                clef.set_attribute("y", "70").unwrap();
-                clef.set_attribute("href", "./img/treble_clef.svg").unwrap();
-            }
-            Clef::Alto => {
-                clef.set_attribute("x", "10").unwrap();
-                clef.set_attribute("y", "80").unwrap();
-                clef.set_attribute("href", "./img/tenor_clef.svg").unwrap();
-            }
-            Clef::Tenor => {
-                clef.set_attribute("x", "10").unwrap();
-                clef.set_attribute("y", "70").unwrap();
-                clef.set_attribute("href", "./img/tenor_clef.svg").unwrap();
-            }
-            Clef::Bass => {
-                clef.set_attribute("x", "10").unwrap();
-                clef.set_attribute("y", "80").unwrap();
-                clef.set_attribute("href", "./img/bass_clef.svg").unwrap();
-            }
-        }
-        card_svg.append_child(&clef).unwrap();
-
-        let center_note = card.clef.center_note();
-
-        let mut center_x = 100;
-        for note in card.notes {
-            let staff_position = center_note.staff_distance(note);
-            let center_y = 100 - (staff_position as i32 * 5);
-            let notehead = document.create_element_ns(SVG_NAMESPACE, "path").unwrap();
-            notehead
-                .set_attribute(
-                    "d",
-                    &format!(
-                        "M {},{} c 0,2 2,4 4,4 4,0 8,-3 8,-6 0,-3 -2,-4 -4,-4 -4,0 -8,3 -8,6",
-                        center_x - 6,
-                        center_y + 1
-                    ),
-                )
-                .unwrap();
-            notehead
-                .set_attribute("style", "fill: #000000; fill-opacity: 1;")
-                .unwrap();
-            card_svg.append_child(&notehead).unwrap();
-            if let Some(accidental) = note.accidental {
-                let element = document.create_element_ns(SVG_NAMESPACE, "path").unwrap();
-                use Accidental as A;
-                element.set_attribute("d", &match accidental{
-                    A::Sharp => format!("m {},{} -9,3 v -3 l 9,-3  m -3,-7 h 1 v 29 h -1  m -4,-28 h 1 v 30 h -1  m 7,-11 -9,3 v -3 l 9,-3", center_x-10, center_y-5),
-                    A::Flat => format!("m {},{} c 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 2,0 3,2 3,4 0,2 -4,8 -7,8 -1,0 -1,-24 -1,-24 z m 6,16 c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z",center_x-17, center_y-18),
-                    A::DoubleSharp => format!("m {},{} c 0,-2 -0,-4 1,-4 2,0 3,-1 4,-2 -1,-1 -2,-2 -4,-2 -1,0 -1,-2 -1,-4 v 0 c 2,0 4,-0 4,1 0,2 1,3 2,4 1,-1 2,-2 2,-4 0,-1 2,-1 4,-1 0,2 0,4 -1,4 -2,0 -3,1 -4,2 1,1 2,2 4,2 1,0 1,2 1,4 -2,0 -4,0 -4,-1 0,-2 -1,-3 -2,-4 -1,1 -2,2 -2,4 0,1 -2,1 -4,1 z", center_x-21, center_y+6),
-                    A::DoubleFlat => format!("m {},{} c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z m 0,-16 c 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 2,0 3,2 3,4 0,2 -4,8 -7,8 0,0 -1,-0 -1,-4 0,0 -3,4 -5,4 -1,0 -1,-24 -1,-24 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 l 1,-0 m 6,4 c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z", center_x-17, center_y-2),
-                    A::Natural => {todo!()}
-                }).unwrap();
-                element
-                    .set_attribute("style", "fill: #000000; fill-opacity: 1;")
-                    .unwrap();
-                card_svg.append_child(&element).unwrap();
-            }
-            let stem = document.create_element_ns(SVG_NAMESPACE, "line").unwrap();
-            if staff_position > 0 {
-                let l_str = &format!("{}.5", center_x - 6);
-                stem.set_attribute("x1", l_str).unwrap();
-                stem.set_attribute("x2", l_str).unwrap();
-                stem.set_attribute("y1", &(center_y + 1).to_string())
-                    .unwrap();
-                stem.set_attribute("y2", &(center_y + 35).max(100).to_string())
-                    .unwrap();
-            } else {
-                let r_str = &format!("{}.5", center_x + 5);
-                stem.set_attribute("x1", r_str).unwrap();
-                stem.set_attribute("x2", r_str).unwrap();
-                stem.set_attribute("y1", &(center_y - 1).to_string())
-                    .unwrap();
-                stem.set_attribute("y2", &(center_y - 35).min(100).to_string())
-                    .unwrap();
-            }
-            stem.set_attribute("style", "stroke: black; stroke-width: 1;")
-                .unwrap();
-            card_svg.append_child(&stem).unwrap();
-
-            let mut prev_ledger_line = 80; // top line position
-            let mut staff_position = staff_position;
-            while staff_position >= 6 {
-                staff_position -= 2;
-                prev_ledger_line -= 10;
-                let y_str = &prev_ledger_line.to_string();
-                let ledger_line = document.create_element_ns(SVG_NAMESPACE, "line").unwrap();
-                ledger_line
-                    .set_attribute("x1", &(center_x - 10).to_string())
-                    .unwrap();
-                ledger_line
-                    .set_attribute("x2", &(center_x + 10).to_string())
-                    .unwrap();
-                ledger_line.set_attribute("y1", y_str).unwrap();
-                ledger_line.set_attribute("y2", y_str).unwrap();
-                ledger_line
-                    .set_attribute("style", "stroke: black; stroke-width: 1;")
-                    .unwrap();
-                card_svg.append_child(&ledger_line).unwrap();
-            }
-
-            let mut prev_ledger_line = 120; // bottom line position
-            let mut staff_position = staff_position;
-            while staff_position <= -6 {
-                staff_position += 2;
-                prev_ledger_line += 10;
-                let y_str = &prev_ledger_line.to_string();
-                let ledger_line = document.create_element_ns(SVG_NAMESPACE, "line").unwrap();
-                ledger_line
-                    .set_attribute("x1", &(center_x - 10).to_string())
-                    .unwrap();
-                ledger_line
-                    .set_attribute("x2", &(center_x + 10).to_string())
-                    .unwrap();
-                ledger_line.set_attribute("y1", y_str).unwrap();
-                ledger_line.set_attribute("y2", y_str).unwrap();
-                ledger_line
-                    .set_attribute("style", "stroke: black; stroke-width: 1;")
-                    .unwrap();
-                card_svg.append_child(&ledger_line).unwrap();
-            }
-            center_x += 70;
-        }
-        card_svg.dyn_into().unwrap()
-    }
-}
-
-#[derive(Clone, PartialEq, Eq)]
-pub struct CelloCardGenerator {
-    finger_pattern_1_allowed: bool,
-    finger_pattern_2_allowed: bool,
-    finger_pattern_34_allowed: bool,
-    finger_pattern_5_allowed: bool,
-    bass_clef: Option<RangeInclusive<u8>>,
-    tenor_clef: Option<RangeInclusive<u8>>,
-    treble_clef: Option<RangeInclusive<u8>>,
-    alto_clef: Option<RangeInclusive<u8>>,
-    half_position_allowed: bool,
-    position_1_allowed: bool,
-    position_2_allowed: bool,
-    position_3_allowed: bool,
-    position_4_allowed: bool,
-    position_5_allowed: bool,
-    position_6_allowed: bool,
-    position_7_allowed: bool,
-    a_string: bool,
-    d_string: bool,
-    g_string: bool,
-    c_string: bool,
-    max_double_accidentals: u8,
-    max_sharps: u8,
-    max_flats: u8,
-    shuffled_order: bool,
-    string_count: u8,
-    max_card_count: Option<NonZeroUsize>
-}
-
-impl CelloCardGenerator {
-    pub const fn no_sharps_flats() -> Self {
-        CelloCardGenerator {
-            finger_pattern_1_allowed: true,
-            finger_pattern_2_allowed: true,
-            finger_pattern_34_allowed: true,
-            finger_pattern_5_allowed: false,
-            bass_clef: Some(
-                Note {
-                    letter: Letter::C,
-                    octave: 2,
-                    accidental: None,
-                }
-                .midi()..=Note {
-                    letter: Letter::C,
-                    octave: 5,
-                    accidental: None,
-                }
-                .midi(),
-            ),
-            tenor_clef: None,
-            treble_clef: None,
-            alto_clef: None,
-            half_position_allowed: true,
-            position_1_allowed: true,
-            position_2_allowed: true,
-            position_3_allowed: true,
-            position_4_allowed: true,
-            position_5_allowed: true,
-            position_6_allowed: true,
-            position_7_allowed: true,
-            a_string: true,
-            d_string: true,
-            g_string: true,
-            c_string: true,
-            max_double_accidentals: 0,
-            max_sharps: 0,
-            max_flats: 0,
-            shuffled_order: false,
-            string_count: 1,
-            max_card_count: NonZeroUsize::new(100)
-        }
-    }
-
-    pub const fn one_flat() -> Self {
-        let mut gen = CelloCardGenerator::no_sharps_flats();
-        gen.max_flats = 1;
-        gen
-    }
-
-    pub const fn one_sharp() -> Self {
-        let mut gen = CelloCardGenerator::one_flat();
-        gen.max_sharps = 1;
-        gen
-    }
-
-    pub const fn two_flats() -> Self {
-        let mut gen = CelloCardGenerator::one_sharp();
-        gen.max_flats = 2;
-        gen
-    }
-
-    pub const fn two_sharps() -> Self {
-        let mut gen = CelloCardGenerator::two_flats();
-        gen.max_sharps = 2;
-        gen
-    }
-
-    pub const fn three_flats() -> Self {
-        let mut gen = CelloCardGenerator::two_sharps();
-        gen.max_flats = 3;
-        gen
-    }
-
-    pub const fn three_sharps() -> Self {
-        let mut gen = CelloCardGenerator::three_flats();
-        gen.max_sharps = 3;
-        gen
-    }
-
-    pub const fn tenor_clef_initial() -> Self {
-        let mut gen = CelloCardGenerator::no_sharps_flats();
-        gen.bass_clef = None;
-        gen.tenor_clef = Some(
-            Note {
-                letter: Letter::C,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 5,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen
-    }
-
-    pub const fn tenor_clef_advanced() -> Self {
-        let mut gen = CelloCardGenerator::three_sharps();
-        gen.bass_clef = None;
-        gen.tenor_clef = Some(
-            Note {
-                letter: Letter::C,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 5,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen
-    }
-
-    pub const fn treble_clef_initial() -> Self {
-        let mut gen = CelloCardGenerator::no_sharps_flats();
-        gen.bass_clef = None;
-        gen.treble_clef = Some(
-            Note {
-                letter: Letter::G,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 5,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen
-    }
-
-    pub const fn treble_clef_advanced() -> Self {
-        let mut gen = CelloCardGenerator::three_sharps();
-        gen.bass_clef = None;
-        gen.treble_clef = Some(
-            Note {
-                letter: Letter::G,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 5,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen
-    }
-
-    pub const fn advanced() -> Self {
-        let mut gen = CelloCardGenerator::three_sharps();
-        gen.tenor_clef = Some(
-            Note {
-                letter: Letter::G,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 3,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen.treble_clef = Some(
-            Note {
-                letter: Letter::G,
-                octave: 3,
-                accidental: None,
-            }
-            .midi()..=Note {
-                letter: Letter::C,
-                octave: 5,
-                accidental: None,
-            }
-            .midi(),
-        );
-        gen.max_double_accidentals = 1;
-        gen.shuffled_order = true;
-        gen.string_count = 2;
-        gen.finger_pattern_5_allowed = true;
-        gen
-    }
-
-    pub const fn impossible() -> Self {
-        CelloCardGenerator {
-            finger_pattern_1_allowed: true,
-            finger_pattern_2_allowed: true,
-            finger_pattern_34_allowed: true,
-            finger_pattern_5_allowed: true,
-            bass_clef: Some(
-                Note {
-                    letter: Letter::C,
-                    octave: 2,
-                    accidental: None,
-                }
-                .midi()..=Note {
-                    letter: Letter::C,
-                    octave: 5,
-                    accidental: None,
-                }
-                .midi(),
-            ),
-            tenor_clef: Some(
-                Note {
-                    letter: Letter::G,
-                    octave: 3,
-                    accidental: None,
-                }
-                .midi()..=Note {
-                    letter: Letter::C,
-                    octave: 5,
-                    accidental: None,
-                }
-                .midi(),
-            ),
-            treble_clef: Some(
-                Note {
-                    letter: Letter::G,
-                    octave: 3,
-                    accidental: None,
-                }
-                .midi()..=Note {
-                    letter: Letter::C,
-                    octave: 6,
-                    accidental: None,
-                }
-                .midi(),
-            ),
-            alto_clef: Some(
-                Note {
-                    letter: Letter::C,
-                    octave: 3,
-                    accidental: None,
-                }
-                .midi()..=Note {
-                    letter: Letter::G,
-                    octave: 4,
-                    accidental: None,
-                }
-                .midi(),
-            ),
-            half_position_allowed: true,
-            position_1_allowed: true,
-            position_2_allowed: true,
-            position_3_allowed: true,
-            position_4_allowed: true,
-            position_5_allowed: true,
-            position_6_allowed: true,
-            position_7_allowed: true,
-            a_string: true,
-            d_string: true,
-            g_string: true,
-            c_string: true,
-            max_double_accidentals: 3,
-            max_sharps: 3,
-            max_flats: 3,
-            shuffled_order: true,
-            string_count: 4,
-            max_card_count: None
-        }
-    }
-
-    pub fn read_settings() -> CelloCardGenerator {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let element = |element_id| {
-            document
-                .get_element_by_id(element_id)
-                .unwrap()
-                .dyn_into::<HtmlInputElement>()
-                .unwrap()
-        };
-        let finger_pattern_1_allowed = element("finger_pattern_1_allowed").checked();
-        let finger_pattern_2_allowed = element("finger_pattern_2_allowed").checked();
-        let finger_pattern_34_allowed = element("finger_pattern_34_allowed").checked();
-        let finger_pattern_5_allowed = element("finger_pattern_5_allowed").checked();
-
-        macro_rules! range {
-            ($id:literal) => {{
-                let min = element(concat!($id, "_clef_min")).value();
-                let max = element(concat!($id, "_clef_max")).value();
-                min.parse::<u8>()
-                    .ok()
-                    .or(min.parse::<Note>().ok().map(|n| n.midi()))
-                    .map(|min| {
-                        max.parse::<u8>()
-                            .ok()
-                            .or(max.parse::<Note>().ok().map(|n| n.midi()))
-                            .map(|max| min..=max)
-                    })
-                    .flatten()
-            }};
-        }
-        let bass_clef = range!("bass");
-        let tenor_clef = range!("tenor");
-        let treble_clef = range!("treble");
-        let alto_clef = range!("alto");
-
-        let half_position_allowed = element("half_position_allowed").checked();
-        let position_1_allowed = element("position_1_allowed").checked();
-        let position_2_allowed = element("position_2_allowed").checked();
-        let position_3_allowed = element("position_3_allowed").checked();
-        let position_4_allowed = element("position_4_allowed").checked();
-        let position_5_allowed = element("position_5_allowed").checked();
-        let position_6_allowed = element("position_6_allowed").checked();
-        let position_7_allowed = element("position_7_allowed").checked();
-
-        let a_string = element("a_string").checked();
-        let d_string = element("d_string").checked();
-        let g_string = element("g_string").checked();
-        let c_string = element("c_string").checked();
-        let max_double_accidentals = element("max_double_accidentals")
-            .value()
-            .parse()
-            .unwrap_or(0);
-        let max_sharps = element("max_sharps").value().parse().unwrap_or(0);
-        let max_flats = element("max_flats").value().parse().unwrap_or(0);
-
-        let shuffled_order = element("shuffled_order").checked();
-        let string_count = element("string_count").value().parse().unwrap_or(0);
-        let max_card_count = element("max_card_count").value().parse::<NonZeroUsize>().ok();
-
-        CelloCardGenerator {
-            finger_pattern_1_allowed,
-            finger_pattern_2_allowed,
-            finger_pattern_34_allowed,
-            finger_pattern_5_allowed,
-            bass_clef,
-            tenor_clef,
-            treble_clef,
-            alto_clef,
-            half_position_allowed,
-            position_1_allowed,
-            position_2_allowed,
-            position_3_allowed,
-            position_4_allowed,
-            position_5_allowed,
-            position_6_allowed,
-            position_7_allowed,
-            a_string,
-            d_string,
-            g_string,
-            c_string,
-            max_double_accidentals,
-            max_sharps,
-            max_flats,
-            shuffled_order,
-            string_count,
-            max_card_count
-        }
-    }
-
-    pub fn write_settings(&self) {
-        let CelloCardGenerator {
-            finger_pattern_1_allowed,
-            finger_pattern_2_allowed,
-            finger_pattern_34_allowed,
-            finger_pattern_5_allowed,
-            bass_clef,
-            tenor_clef,
-            treble_clef,
-            alto_clef,
-            half_position_allowed,
-            position_1_allowed,
-            position_2_allowed,
-            position_3_allowed,
-            position_4_allowed,
-            position_5_allowed,
-            position_6_allowed,
-            position_7_allowed,
-            a_string,
-            d_string,
-            g_string,
-            c_string,
-            max_double_accidentals,
-            max_sharps,
-            max_flats,
-            shuffled_order,
-            string_count,
-            max_card_count
-        } = self.clone();
-        let document = web_sys::window().unwrap().document().unwrap();
-        let element = |element_id| {
-            document
-                .get_element_by_id(element_id)
-                .unwrap()
-                .dyn_into::<HtmlInputElement>()
-                .unwrap()
-        };
-        element("finger_pattern_1_allowed").set_checked(finger_pattern_1_allowed);
-        element("finger_pattern_2_allowed").set_checked(finger_pattern_2_allowed);
-        element("finger_pattern_34_allowed").set_checked(finger_pattern_34_allowed);
-        element("finger_pattern_5_allowed").set_checked(finger_pattern_5_allowed);
-
-        if let Some(range) = bass_clef {
-            element("bass_clef_min").set_value(&Note::from_midi(*range.start()).to_string());
-            element("bass_clef_max").set_value(&Note::from_midi(*range.end()).to_string());
-            crate::update_bass_notes();
-        } else {
-            element("bass_clef_min").set_value("");
-            element("bass_clef_max").set_value("");
-        }
-        if let Some(range) = tenor_clef {
-            element("tenor_clef_min").set_value(&Note::from_midi(*range.start()).to_string());
-            element("tenor_clef_max").set_value(&Note::from_midi(*range.end()).to_string());
-            crate::update_tenor_notes();
-        } else {
-            element("tenor_clef_min").set_value("");
-            element("tenor_clef_max").set_value("");
-        }
-        if let Some(range) = treble_clef {
-            element("treble_clef_min").set_value(&Note::from_midi(*range.start()).to_string());
-            element("treble_clef_max").set_value(&Note::from_midi(*range.end()).to_string());
-            crate::update_treble_notes();
-        } else {
-            element("treble_clef_min").set_value("");
-            element("treble_clef_max").set_value("");
-        }
-        if let Some(range) = alto_clef {
-            element("alto_clef_min").set_value(&Note::from_midi(*range.start()).to_string());
-            element("alto_clef_max").set_value(&Note::from_midi(*range.end()).to_string());
-            crate::update_alto_notes();
-        } else {
-            element("alto_clef_min").set_value("");
-            element("alto_clef_max").set_value("");
-        }
-
-        element("half_position_allowed").set_checked(half_position_allowed);
-        element("position_1_allowed").set_checked(position_1_allowed);
-        element("position_2_allowed").set_checked(position_2_allowed);
-        element("position_3_allowed").set_checked(position_3_allowed);
-        element("position_4_allowed").set_checked(position_4_allowed);
-        element("position_5_allowed").set_checked(position_5_allowed);
-        element("position_6_allowed").set_checked(position_6_allowed);
-        element("position_7_allowed").set_checked(position_7_allowed);
-
-        element("a_string").set_checked(a_string);
-        element("d_string").set_checked(d_string);
-        element("g_string").set_checked(g_string);
-        element("c_string").set_checked(c_string);
-        element("max_double_accidentals").set_value(&max_double_accidentals.to_string());
-        element("max_sharps").set_value(&max_sharps.to_string());
-        element("max_flats").set_value(&max_flats.to_string());
-
-        element("shuffled_order").set_checked(shuffled_order);
-        element("string_count").set_value(&string_count.to_string());
-
-        match max_card_count {
-            Some(x) => element("max_card_count").set_value(&x.to_string()),
-            None => element("max_card_count").set_value(""),
-        }
-    }
-
-    pub fn card_generator<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Card> {
-        let mut finger_patterns = Vec::with_capacity(4);
-        if self.finger_pattern_1_allowed {
-            finger_patterns.push([
-                Interval::new(IntervalQuality::Minor, 2),
-                Interval::new(IntervalQuality::Minor, 3),
-            ]);
-        }
-        if self.finger_pattern_2_allowed {
-            finger_patterns.push([
-                Interval::new(IntervalQuality::Major, 2),
-                Interval::new(IntervalQuality::Minor, 3),
-            ]);
-        }
-        if self.finger_pattern_34_allowed {
-            finger_patterns.push([
-                Interval::new(IntervalQuality::Major, 2),
-                Interval::new(IntervalQuality::Major, 3),
-            ]);
-        }
-        if self.finger_pattern_5_allowed {
-            finger_patterns.push([
-                Interval::new(IntervalQuality::Augmented, 2),
-                Interval::new(IntervalQuality::Major, 3),
-            ]);
-        }
-
-        let mut clefs = Vec::with_capacity(4);
-        if let Some(range) = &self.bass_clef {
-            clefs.push((Clef::Bass, range.clone()));
-        }
-        if let Some(range) = &self.treble_clef {
-            clefs.push((Clef::Treble, range.clone()));
-        }
-        if let Some(range) = &self.tenor_clef {
-            clefs.push((Clef::Tenor, range.clone()));
-        }
-        if let Some(range) = &self.alto_clef {
-            clefs.push((Clef::Alto, range.clone()));
-        }
-
-        let CelloCardGenerator {
-            half_position_allowed,
-            position_1_allowed,
-            position_2_allowed,
-            position_3_allowed,
-            position_4_allowed,
-            position_5_allowed,
-            position_6_allowed,
-            position_7_allowed,
-            a_string,
-            d_string,
-            g_string,
-            c_string,
-            max_double_accidentals,
-            max_sharps,
-            max_flats,
-            finger_pattern_1_allowed: _,
-            finger_pattern_2_allowed: _,
-            finger_pattern_34_allowed: _,
-            finger_pattern_5_allowed: _,
-            bass_clef: _,
-            tenor_clef: _,
-            treble_clef: _,
-            alto_clef: _,
-            shuffled_order,
-            string_count,
-            max_card_count
-        } = self.clone();
-        let offsets = [
-            Interval {
-                interval: 1,
-                quality: IntervalQuality::Perfect,
-            },
-            Interval {
-                interval: 5,
-                quality: IntervalQuality::Perfect,
-            },
-            Interval {
-                interval: 9,
-                quality: IntervalQuality::Major,
-            },
-            Interval {
-                interval: 13,
-                quality: IntervalQuality::Major,
-            },
-        ];
-        let rng = RefCell::new(rng);
-        let mut cards: Vec<_> =
-            (0..=255/*C#2 to A4*/)
-            .flat_map(|start_note_midi| {
-                let finger_patterns_ref = &finger_patterns;
-                Note::from_midi(start_note_midi)
-                    .all_enharmonic_equivalents()
-                    .filter(|start_note| {
-                        if a_string
-                        /*A#3-A4: 58..=69*/
-                        {
-                            if match start_note {
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 3,
-                                    accidental: Some(Accidental::Sharp),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::B,
-                                    octave: 3,
-                                    accidental: Some(Accidental::Flat),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::B,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_1_allowed,
-                                Note {
-                                    letter: Letter::C,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_2_allowed,
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_3_allowed,
-                                Note {
-                                    letter: Letter::E,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_4_allowed,
-                                Note {
-                                    letter: Letter::F,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_5_allowed,
-                                Note {
-                                    letter: Letter::G,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_6_allowed,
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_7_allowed,
-                                _ => false,
-                            } {
-                                return true;
-                            }
-                        }
-                        if d_string {
-                            if match start_note {
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 3,
-                                    accidental: Some(Accidental::Sharp),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::E,
-                                    octave: 3,
-                                    accidental: Some(Accidental::Flat),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::E,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_1_allowed,
-                                Note {
-                                    letter: Letter::F,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_2_allowed,
-                                Note {
-                                    letter: Letter::G,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_3_allowed,
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_4_allowed,
-                                Note {
-                                    letter: Letter::B,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_5_allowed,
-                                Note {
-                                    letter: Letter::C,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_6_allowed,
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 4,
-                                    accidental: _,
-                                } => position_7_allowed,
-                                _ => false,
-                            } {
-                                return true;
-                            }
-                        }
-                        if g_string {
-                            if match start_note {
-                                Note {
-                                    letter: Letter::G,
-                                    octave: 2,
-                                    accidental: Some(Accidental::Sharp),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 2,
-                                    accidental: Some(Accidental::Flat),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_1_allowed,
-                                Note {
-                                    letter: Letter::B,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_2_allowed,
-                                Note {
-                                    letter: Letter::C,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_3_allowed,
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_4_allowed,
-                                Note {
-                                    letter: Letter::E,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_5_allowed,
-                                Note {
-                                    letter: Letter::F,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_6_allowed,
-                                Note {
-                                    letter: Letter::G,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_7_allowed,
-                                _ => false,
-                            } {
-                                return true;
-                            }
-                        }
-                        if c_string {
-                            if match start_note {
-                                Note {
-                                    letter: Letter::C,
-                                    octave: 2,
-                                    accidental: Some(Accidental::Sharp),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 2,
-                                    accidental: Some(Accidental::Flat),
-                                } => half_position_allowed,
-                                Note {
-                                    letter: Letter::D,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_1_allowed,
-                                Note {
-                                    letter: Letter::E,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_2_allowed,
-                                Note {
-                                    letter: Letter::F,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_3_allowed,
-                                Note {
-                                    letter: Letter::G,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_4_allowed,
-                                Note {
-                                    letter: Letter::A,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_5_allowed,
-                                Note {
-                                    letter: Letter::B,
-                                    octave: 2,
-                                    accidental: _,
-                                } => position_6_allowed,
-                                Note {
-                                    letter: Letter::C,
-                                    octave: 3,
-                                    accidental: _,
-                                } => position_7_allowed,
-                                _ => false,
-                            } {
-                                return true;
-                            }
-                        }
-                        false
-                    })
-                    .flat_map(|start_note| {
-                        let start_note: Note = start_note.clone();
-                        finger_patterns_ref
-                            .iter()
-                            .filter_map(move |intervals| {
-                                if let (Some(note2), Some(note3)) =
-                                    (start_note + intervals[0], start_note + intervals[1])
-                                {
-                                    Some([start_note, note2, note3])
-                                } else {
-                                    None
-                                }
-                            })
-                            .filter(|notes| {
-                                let mut sharps = 0;
-                                let mut flats = 0;
-                                let mut double = 0;
-                                for note in notes {
-                                    match note.accidental {
-                                        Some(Accidental::Flat) => {
-                                            flats += 1;
-                                        }
-                                        Some(Accidental::DoubleFlat) => {
-                                            double += 1;
-                                            flats += 1;
-                                        }
-                                        Some(Accidental::DoubleSharp) => {
-                                            double += 1;
-                                            sharps += 1;
-                                        }
-                                        Some(Accidental::Sharp) => {
-                                            sharps += 1;
-                                        }
-                                        None | Some(Accidental::Natural) => {}
-                                    }
-                                }
-                                if sharps > max_sharps {
-                                    return false;
-                                }
-                                if flats > max_flats {
-                                    return false;
-                                }
-                                if double > max_double_accidentals {
-                                    return false;
-                                }
-                                true
-                            })
-                    })
-            })
-            .flat_map(|notes| {
-                let rng = &rng;
-                clefs.iter().filter_map(move |(clef, range)| {
-                    let notes = match (shuffled_order, string_count) {
-                        (false, 0) => notes,
-                        (true, _) => {
-                            let mut notes = notes.map(|n: Note| {
-                                n.add(offsets[rng.borrow_mut().gen_range(0..string_count) as usize])
-                                    .unwrap_or(n)
-                            });
-                            notes.shuffle(&mut*rng.borrow_mut());
-                            notes
-                        }
-                        (false, _) => {
-                            let mut notes = notes.map(|n: Note| {
-                                n.add(offsets[rng.borrow_mut().gen_range(0..string_count) as usize])
-                                    .unwrap_or(n)
-                            });
-                            notes.sort_by_key(|n| n.midi());
-                            notes
-                        }
-                    };
-                    if notes.iter().all(|note| range.contains(&note.midi())) {
-                        return Some(Card {
-                            clef: clef.clone(),
-                            notes,
-                        });
-                    }
-                    None
-                })
-            })
-            .collect();
-        cards.shuffle(rng.into_inner());
-        if let Some(x) = max_card_count{
-            cards.truncate(x.get());
-        }
-        cards
-    }
-}
-#[cfg(test)]
-#[test]
-fn cello_card_generator() {
-    let mut rng = thread_rng();
-    CelloCardGenerator::no_sharps_flats()
-        .card_generator(&mut rng)
-        .into_iter()
-        .for_each(|c| {
-            println!("{c:?}");
-        });
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Letter {
-    C = 0,
-    D = 1,
-    E = 2,
-    F = 3,
-    G = 4,
-    A = 5,
-    B = 6,
-}
-
-impl From<Letter> for char {
-    #[inline]
-    fn from(letter: Letter) -> Self {
-        use Letter as L;
-        match letter {
-            L::A => 'A',
-            L::B => 'B',
-            L::C => 'C',
-            L::D => 'D',
-            L::E => 'E',
-            L::F => 'F',
-            L::G => 'G',
-        }
-    }
-}
-
-impl TryFrom<char> for Letter {
-    type Error = ();
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        use Letter as L;
-        Ok(match value {
-            'A' => L::A,
-            'B' => L::B,
-            'C' => L::C,
-            'D' => L::D,
-            'E' => L::E,
-            'F' => L::F,
-            'G' => L::G,
-            _ => {
-                return Err(());
-            }
-        })
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-enum Clef {
-    Treble,
-    Alto,
-    Tenor,
-    Bass,
-}
-
-impl FromStr for Clef {
-    type Err = ();
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use Clef as C;
-        Ok(match s {
-            "B4" => C::Treble,
-            "A3" => C::Tenor,
-            "C4" => C::Alto,
-            "D3" => C::Bass,
-            _ => {
-                return Err(());
-            }
-        })
-    }
-}
-
-impl Clef {
-    const fn center_note(self) -> Note {
-        use Clef as C;
-        use Letter as L;
-        let accidental = None;
-        match self {
-            C::Treble => Note {
-                letter: L::B,
-                octave: 4,
-                accidental,
-            },
-            C::Alto => Note {
-                letter: L::C,
-                octave: 4,
-                accidental,
-            },
-            C::Tenor => Note {
-                letter: L::A,
-                octave: 3,
-                accidental,
-            },
-            C::Bass => Note {
-                letter: L::D,
-                octave: 3,
-                accidental,
-            },
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Accidental {
-    DoubleFlat = -2,
-    Flat = -1,
-    Natural = 0,
-    Sharp = 1,
-    DoubleSharp = 2,
-}
-
-impl TryFrom<i8> for Accidental {
-    type Error = ();
-    #[inline]
-    fn try_from(val: i8) -> Result<Accidental, Self::Error> {
-        use Accidental as A;
-        Ok(match val {
-            -2 => A::DoubleFlat,
-            -1 => A::Flat,
-            0 => A::Natural,
-            1 => A::Sharp,
-            2 => A::DoubleSharp,
-            _ => {
-                return Err(());
-            }
-        })
-    }
-}
-
-impl Accidental {
-    fn to_str(self) -> &'static str {
-        use Accidental as A;
-        match self {
-            A::DoubleFlat => "bb",
-            A::Flat => "b",
-            A::Natural => "",
-            A::Sharp => "#",
-            A::DoubleSharp => "##",
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Note {
-    letter: Letter,
-    octave: u8,
-    accidental: Option<Accidental>,
-}
-
-use std::str::FromStr;
-
-#[allow(clippy::iter_nth_zero)]
-impl FromStr for Note {
-    type Err = ();
-    fn from_str(src: &str) -> Result<Note, Self::Err> {
-        let mut src = src.chars();
-        let letter = src.next().ok_or(())?.try_into()?;
-        let mut tmp = src.clone();
-        let accidental = match tmp.next() {
-            Some('#') => match tmp.next() {
-                Some('#') => {
-                    src.nth(1);
-                    Some(Accidental::DoubleSharp)
-                }
-                _ => {
-                    src.nth(0);
-                    Some(Accidental::Sharp)
-                }
-            },
-            Some('b') => match tmp.clone().next() {
-                Some('b') => {
-                    src.nth(1);
-                    Some(Accidental::DoubleFlat)
-                }
-                _ => {
-                    src.nth(0);
-                    Some(Accidental::Flat)
-                }
-            },
-            Some('n') => {
-                src.nth(0);
-                Some(Accidental::Natural)
-            }
-            _ => None,
-        };
-        tmp = src.clone();
-        let mut octave = 0;
-        while let Some(digit) = tmp.next().and_then(|d| d.to_digit(10)) {
-            src.next();
-            octave *= 10;
-            octave += digit as u8;
-        }
-        Ok(Note {
-            letter,
-            octave,
-            accidental,
-        })
-    }
-}
-
-impl ToString for Note {
-    fn to_string(&self) -> String {
-        let l: char = self.letter.into();
-        if let Some(a) = self.accidental {
-            format!("{}{}{}", l, a.to_str(), self.octave)
-        } else {
-            format!("{}{}", l, self.octave)
-        }
-    }
-}
-
-impl Note {
-    /// How far up the staff do you have to go to get to the other note?
-    const fn staff_distance(self, other: Note) -> i8 {
-        let octave_distance = (other.octave as i8 - self.octave as i8) * 7;
-        let letter_distance = other.letter as i8 - self.letter as i8;
-        octave_distance + letter_distance
-    }
-
-    const fn midi(self) -> u8 {
-        // note: c4 is 60
-        use Letter as L;
-        return (match self.letter {
-            L::C => 0i8,
-            L::D => 2,
-            L::E => 4,
-            L::F => 5,
-            L::G => 7,
-            L::A => 9,
-            L::B => 11,
-        }
-        .wrapping_add(if let Some(accidental) = self.accidental {
-            accidental as i8
-        } else {
-            0
-        }) as u8)
-            .wrapping_add(12u8.wrapping_mul(self.octave.wrapping_add(1)));
-    }
-
-    pub fn from_midi(midi: u8) -> Note {
-        let octave = midi / 12;
-        let after_octave = midi % 12;
-        let note_map = [0, 2, 4, 5, 7, 9, 11];
-        let reverse_note_map = [L::C, L::D, L::E, L::F, L::G, L::A, L::B];
-        let note = note_map.binary_search(&after_octave);
-        use Letter as L;
-        let (letter, accidental) = match note {
-            Ok(val) => (reverse_note_map[val], None),
-            Err(next_note) => (reverse_note_map[next_note - 1], {
-                assert!(
-                    after_octave - note_map[next_note - 1] == 1,
-                    "should have been found in the note map"
-                );
-                Some(Accidental::Sharp)
-            }),
-        };
-        Note {
-            octave: octave.wrapping_sub(1),
-            letter,
-            accidental,
-        }
-    }
-
-    fn enharmonic_equivalent(self, letter: Letter) -> Option<Note> {
-        let letter_distance = letter as i8 - self.letter as i8;
-
-        let octave = match letter_distance {
-            5..=7 => self.octave.wrapping_sub(1),
-            -7..=-5 => self.octave.wrapping_add(1),
-            _ => self.octave,
-        };
-        let mut new_note = Note {
-            octave,
-            letter,
-            accidental: None,
-        };
-        let adjustment = (new_note.midi()).wrapping_sub(self.midi()) as i8;
-        match adjustment {
-            -2 => {
-                new_note.accidental = Some(Accidental::DoubleSharp);
-                Some(new_note)
-            }
-            -1 => {
-                new_note.accidental = Some(Accidental::Sharp);
-                Some(new_note)
-            }
-            0 => Some(new_note),
-            1 => {
-                new_note.accidental = Some(Accidental::Flat);
-                Some(new_note)
-            }
-            2 => {
-                new_note.accidental = Some(Accidental::DoubleFlat);
-                Some(new_note)
-            }
-            //there is no enharmonic equivalent for this letter.
-            _ => {
-                println!("{self:?} has no enharmonic_equivalent {letter:?} (adjustmnet {adjustment} needed)", );
-                None
-            }
-        }
-    }
-
-    fn all_enharmonic_equivalents(self) -> impl Iterator<Item = Note> {
-        use Letter::*;
-        [C, D, E, F, G, A, B]
-            .into_iter()
-            .filter_map(move |letter| self.enharmonic_equivalent(letter))
-    }
-}
-
-#[cfg(test)]
-#[test]
-fn enharmonic_tests() {
-    assert_eq!(
-        "Cbb4"
-            .parse::<Note>()
-            .unwrap()
-            .enharmonic_equivalent(Letter::B)
-            .unwrap(),
-        "Bb3".parse().unwrap()
-    );
-}
-
-use std::ops::{Add, DerefMut, RangeInclusive};
-impl Add<Interval> for Note {
-    type Output = Option<Note>;
-    fn add(self, interval: Interval) -> Option<Note> {
-        let other_midi = self.midi().wrapping_add(interval.midi_offset() as u8);
-        let delta_letter = if interval.interval > 0 {
-            interval.interval - 1
-        } else {
-            interval.interval + 1
-        };
-        use Letter as L;
-        let new_letter = match (self.letter as i8 + delta_letter) % 7 {
-            0 => L::C,
-            1 => L::D,
-            2 => L::E,
-            3 => L::F,
-            4 => L::G,
-            5 => L::A,
-            6 => L::B,
-            _ => unreachable!("_%7 is in the range 0..=6"),
-        };
-        Note::from_midi(other_midi).enharmonic_equivalent(new_letter)
-    }
-}
-
-#[cfg(test)]
-#[test]
-fn midi_notes() {
-    assert_eq!("C4".parse::<Note>().unwrap().midi(), 60);
-    assert_eq!("B##3".parse::<Note>().unwrap().midi(), 61);
-    assert_eq!("Abb4".parse::<Note>().unwrap().midi(), 67);
-}
-
-#[derive(Debug, Clone, Copy)]
-pub enum IntervalQuality {
-    Major,
-    Perfect,
-    Minor,
-    Diminished,
-    Augmented,
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct Interval {
-    quality: IntervalQuality,
-    interval: i8,
-}
-
-impl Interval {
-    pub fn new(quality: IntervalQuality, interval: i8) -> Self {
-        match (interval % 7, quality) {
-            (1 | 4 | 5, IntervalQuality::Major | IntervalQuality::Minor) => {
-                panic!("{}'s cannot be major or minor", interval)
-            }
-            (2 | 3 | 6 | 0, IntervalQuality::Perfect) => {
-                panic!("{}'s cannot be perfect", interval)
-            }
-            _ => Interval { quality, interval },
-        }
-    }
-
-    pub fn midi_offset(self) -> i8 {
-        use IntervalQuality as Q;
-        let interval: u8 = self.interval.abs() as u8 - 1;
-        let octaves = (interval / 7) as i8;
-        let interval_index = (interval % 7) as usize;
-        let major_perfect_offsets = [0i8, 2, 4, 5, 7, 9, 11];
-        println!("{self:?}",);
-        println!("octaves: {octaves:?}",);
-        println!("interval_index: {interval_index:?}",);
-        let offset = octaves * 12
-            + match self.quality {
-                Q::Major | Q::Perfect => major_perfect_offsets[interval_index],
-                Q::Diminished => match interval_index {
-                    0 | 3 | 4 => major_perfect_offsets[interval_index] - 1,
-                    _ => major_perfect_offsets[interval_index] - 2,
-                },
-                Q::Minor => major_perfect_offsets[interval_index] - 1,
-                Q::Augmented => major_perfect_offsets[interval_index] + 1,
-            };
-        if self.interval > 0 {
-            offset as i8
-        } else {
-            -(offset as i8)
-        }
-    }
-}
-
-#[cfg(test)]
-#[test]
-fn interval_tests() {
-    use Accidental::*;
-    use Letter::*;
-    let test_interval = Interval {
-        interval: 1,
-        quality: IntervalQuality::Perfect,
-    };
-    for letter in [A, B, C, D, E, F, G] {
-        for octave in 0..=8 {
-            let note = Note {
-                letter,
-                octave,
-                accidental: None,
-            };
-            println!("{note:?}");
-            assert_eq!(note.midi(), (note + test_interval).unwrap().midi());
-            for accidental in [DoubleFlat, DoubleSharp, Flat, Sharp, Natural] {
-                let note = Note {
-                    letter,
-                    octave,
-                    accidental: Some(accidental),
-                };
-                println!("{note:?}");
-                assert_eq!(note.midi(), (note + test_interval).unwrap().midi());
-            }
-        }
-    }
-    assert_eq!(Interval::new(IntervalQuality::Perfect, 8).midi_offset(), 12);
-    assert_eq!(Interval::new(IntervalQuality::Major, 10).midi_offset(), 16);
-    assert_eq!(
-        Interval::new(IntervalQuality::Diminished, 7).midi_offset(),
-        9
-    );
-    assert_eq!(Interval::new(IntervalQuality::Perfect, 1).midi_offset(), 0);
-    assert_eq!(Interval::new(IntervalQuality::Major, -3).midi_offset(), -4);
-}
+use rand::prelude::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+use web_sys::{Element, HtmlInputElement, HtmlSelectElement, SvgsvgElement};
+
+use crate::scales::{self, Drill, Scale};
+use crate::schedule::Schedule;
+use crate::svg::Svg;
+use crate::theory::{Accidental, Clef, Interval, IntervalQuality, KeySignature, Letter, Note};
+use crate::SVG_NAMESPACE;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    clef: Clef,
+    kind: CardKind,
+    notes: Vec<Note>,
+    key: KeySignature,
+    fingering: Option<Fingering>,
+    /// For scale and arpeggio cards, the drill and which part of it this is.
+    drill: Option<DrillPart>,
+    /// For natural harmonic cards, the string and where it is touched.
+    harmonic: Option<NaturalHarmonic>,
+    /// Whether a harmonic's sounding pitch is printed small under it, rather
+    /// than only given with the answer.
+    sounding_shown: bool,
+}
+
+/// One card's worth of a drill too long to fit on a single card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrillPart {
+    drill: Drill,
+    /// Counting from 1.
+    part: usize,
+    parts: usize,
+}
+
+/// What a card asks the student to read. Each kind has its own number of
+/// notes, which is how cards read from text get their kind; anything longer
+/// than a scale fragment is part of a drill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardKind {
+    /// One note to name.
+    SingleNote,
+    /// Two notes to name the interval between.
+    Interval,
+    /// Three notes under one hand: name the string and position.
+    Position,
+    /// All four fingers down in one position.
+    FullPosition,
+    /// The first five notes of a major scale, going up or down.
+    ScaleFragment,
+    /// Part of a scale or arpeggio, with fingerings and shifts.
+    Drill,
+    /// Two notes on neighbouring strings played together, written as one
+    /// chord: name the fingers, strings and position. Read from text as two
+    /// notes joined by `+`.
+    DoubleStop,
+    /// An open string touched at a node, drawn as a diamond: name the string
+    /// and the pitch that sounds.
+    NaturalHarmonic,
+    /// A stopped note with a diamond a fourth above it where the string is
+    /// touched: name the string, position and the pitch that sounds.
+    ArtificialHarmonic,
+}
+
+impl CardKind {
+    pub const ALL: [CardKind; 9] = {
+        use CardKind::*;
+        [
+            SingleNote,
+            Interval,
+            Position,
+            FullPosition,
+            ScaleFragment,
+            Drill,
+            DoubleStop,
+            NaturalHarmonic,
+            ArtificialHarmonic,
+        ]
+    };
+
+    pub fn from_note_count(count: usize) -> Option<CardKind> {
+        match count {
+            0 => None,
+            1..=5 => CardKind::ALL.get(count - 1).copied(),
+            _ => Some(CardKind::Drill),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut elements = src.split(':').peekable();
+        let clef = elements.next().and_then(|s| s.parse().ok()).ok_or(())?;
+        let mut notes = Vec::new();
+        let kind = if let Some((low, high)) = elements.peek().and_then(|s| s.split_once('+')) {
+            notes.extend([
+                low.parse::<Note>().map_err(|_| ())?,
+                high.parse().map_err(|_| ())?,
+            ]);
+            elements.next();
+            CardKind::DoubleStop
+        } else {
+            while let Some(note) = elements.peek().and_then(|s| s.parse().ok()) {
+                notes.push(note);
+                elements.next();
+            }
+            CardKind::from_note_count(notes.len()).ok_or(())?
+        };
+        Ok(Card {
+            clef,
+            kind,
+            notes,
+            key: match elements.next() {
+                Some(key) => key.parse().map_err(|_| ())?,
+                None => KeySignature::default(),
+            },
+            fingering: None,
+            drill: None,
+            harmonic: None,
+            sounding_shown: false,
+        })
+    }
+}
+
+pub fn status_card(source: &str) -> Element {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let card: Element = document.create_element("div").unwrap();
+    card.set_attribute("width", "100%").unwrap();
+    card.set_attribute("height", "100%").unwrap();
+    card.set_attribute("style", "font-size: 24px; text-align: center;")
+        .unwrap();
+    card.set_inner_html(source);
+    card
+}
+
+impl Card {
+    /// Draws the card without touching the DOM.
+    pub fn svg(&self) -> Svg {
+        self.render(false)
+    }
+
+    /// Draws the card with its answer: finger numbers over the notes and the
+    /// string and position under the staff.
+    pub fn answer_svg(&self) -> Svg {
+        self.render(true)
+    }
+
+    fn render(&self, reveal: bool) -> Svg {
+        let mut svg = Svg::new(300, 200);
+
+        let mut current_height = 80;
+        for _ in 0..5 {
+            svg.line(0, current_height, 300, current_height);
+            current_height += 10;
+        }
+
+        let center_note = self.clef.center_note();
+
+        let (glyph, line_note) = self.clef.glyph();
+        let line_y = 100 - (center_note.staff_distance(line_note) as i32 * 5);
+        svg.glyph(10, line_y - glyph.line_offset(), glyph.path());
+        if let Clef::TrebleOctaveDown = self.clef {
+            svg.text(21, line_y + 35, 10, "8");
+        }
+
+        let mut key_x = 65;
+        for (note, accidental) in self.key.placements(self.clef) {
+            let key_y = 100 - (center_note.staff_distance(note) as i32 * 5);
+            svg.path(&accidental.path(key_x, key_y));
+            key_x += 9;
+        }
+        // double stops and artificial harmonics are drawn as one chord, every
+        // other card a note at a time
+        let columns: Vec<Vec<usize>> = if matches!(
+            self.kind,
+            CardKind::DoubleStop | CardKind::ArtificialHarmonic
+        ) {
+            vec![(0..self.notes.len()).collect()]
+        } else {
+            (0..self.notes.len()).map(|i| vec![i]).collect()
+        };
+        // leave room for long key signatures by squeezing the notes together
+        let mut center_x = 100.max(key_x + 21);
+        let note_spacing = match columns.len() {
+            1 => {
+                center_x = center_x.max(150);
+                0
+            }
+            count => 70.min((270 - center_x) / (count as i32 - 1)),
+        };
+
+        let accidentals = self.displayed_accidentals();
+        for column in &columns {
+            let mut column = column.clone();
+            column.sort_by_key(|&i| center_note.staff_distance(self.notes[i]));
+            let staff_positions: Vec<i8> = column
+                .iter()
+                .map(|&i| center_note.staff_distance(self.notes[i]))
+                .collect();
+            let lowest = staff_positions[0];
+            let highest = staff_positions[staff_positions.len() - 1];
+            let (top_y, bottom_y) = (100 - highest as i32 * 5, 100 - lowest as i32 * 5);
+            // the stem points away from whichever note is further from the middle line
+            let stem_down = highest + lowest > 0;
+
+            // two notes a second apart can't sit on the same side of the stem,
+            // so one of them moves across it
+            let mut head_x = vec![center_x; column.len()];
+            for k in 1..column.len() {
+                if staff_positions[k] - staff_positions[k - 1] == 1 && head_x[k - 1] == center_x {
+                    if stem_down {
+                        head_x[k - 1] = center_x - 11;
+                    } else {
+                        head_x[k] = center_x + 11;
+                    }
+                }
+            }
+            // accidentals close enough to collide are stacked leftwards from the top
+            let left_x = *head_x.iter().min().unwrap();
+            let mut accidental_x = vec![left_x; column.len()];
+            let mut above: Option<(i8, i32)> = None;
+            for k in (0..column.len()).rev() {
+                if accidentals[column[k]].is_some() {
+                    if let Some((_, x)) =
+                        above.filter(|&(position, _)| position - staff_positions[k] < 6)
+                    {
+                        accidental_x[k] = x - 12;
+                    }
+                    above = Some((staff_positions[k], accidental_x[k]));
+                }
+            }
+
+            for (k, &i) in column.iter().enumerate() {
+                let center_y = 100 - (staff_positions[k] as i32 * 5);
+                // a diamond where the string is only touched
+                let touched = match self.kind {
+                    CardKind::NaturalHarmonic => true,
+                    CardKind::ArtificialHarmonic => i == 1,
+                    _ => false,
+                };
+                if touched {
+                    svg.outline(&format!(
+                        "M {},{} l 6,-5 6,5 -6,5 z",
+                        head_x[k] - 6,
+                        center_y
+                    ));
+                } else {
+                    svg.path(&format!(
+                        "M {},{} c 0,2 2,4 4,4 4,0 8,-3 8,-6 0,-3 -2,-4 -4,-4 -4,0 -8,3 -8,6",
+                        head_x[k] - 6,
+                        center_y + 1
+                    ));
+                }
+                if let Some(accidental) = accidentals[i] {
+                    svg.path(&accidental.path(accidental_x[k], center_y));
+                }
+            }
+            if stem_down {
+                let l_str = &format!("{}.5", center_x - 6);
+                svg.line(l_str, top_y + 1, l_str, (bottom_y + 35).max(100));
+            } else {
+                let r_str = &format!("{}.5", center_x + 5);
+                svg.line(r_str, bottom_y - 1, r_str, (top_y - 35).min(100));
+            }
+
+            let right_x = *head_x.iter().max().unwrap();
+            let mut prev_ledger_line = 80; // top line position
+            let mut ledger_position = highest;
+            while ledger_position >= 6 {
+                ledger_position -= 2;
+                prev_ledger_line -= 10;
+                svg.line(
+                    left_x - 10,
+                    prev_ledger_line,
+                    right_x + 10,
+                    prev_ledger_line,
+                );
+            }
+
+            let mut prev_ledger_line = 120; // bottom line position
+            let mut ledger_position = lowest;
+            while ledger_position <= -6 {
+                ledger_position += 2;
+                prev_ledger_line += 10;
+                svg.line(
+                    left_x - 10,
+                    prev_ledger_line,
+                    right_x + 10,
+                    prev_ledger_line,
+                );
+            }
+
+            let (top, bottom) = if stem_down {
+                (top_y - 5, (bottom_y + 35).max(100))
+            } else {
+                ((top_y - 35).min(100), bottom_y + 5)
+            };
+            if let (true, Some(sounding)) = (self.sounding_shown, self.sounding()) {
+                svg.text(
+                    center_x,
+                    bottom.max(120) + 16,
+                    10,
+                    &format!("sounds {}", sounding),
+                );
+            }
+            if let (true, Some(fingering)) = (reveal, &self.fingering) {
+                // the fingers of a chord are stacked in the same order as its notes
+                for (k, &i) in column.iter().enumerate() {
+                    let (finger, string) = fingering.notes[i];
+                    let baseline = top.min(80) - 8 - 14 * k as i32;
+                    if finger == 0 {
+                        // the thumb sign: a ring on a short stem, sitting where the digit would
+                        svg.circle(center_x, baseline - 8, 4);
+                        svg.line(center_x, baseline - 4, center_x, baseline);
+                    } else {
+                        svg.text(center_x, baseline, 14, &finger.to_string());
+                    }
+                    if column.len() == 1
+                        && fingering
+                            .notes
+                            .iter()
+                            .any(|&(_, other)| other != fingering.string)
+                    {
+                        svg.text(center_x, bottom.max(120) + 16, 12, string.name());
+                    }
+                    if let Some((_, position)) = fingering.shifts.iter().find(|&&(at, _)| at == i) {
+                        svg.text(center_x, bottom.max(120) + 28, 10, position.label());
+                    }
+                }
+            }
+            center_x += note_spacing;
+        }
+
+        if let (true, Some(fingering), Some(part)) = (reveal, &self.fingering, self.drill) {
+            let title = format!("{} ({}/{})", part.drill.name(), part.part, part.parts);
+            svg.text(150, 180, 14, &title);
+            let start = format!(
+                "from the {} string, {} position",
+                fingering.string.name(),
+                fingering.position.label()
+            );
+            svg.text(150, 195, 10, &start);
+        } else if let (true, Some(fingering)) = (reveal, &self.fingering) {
+            let label = if self.kind == CardKind::DoubleStop {
+                let strings: Vec<_> = fingering
+                    .notes
+                    .iter()
+                    .map(|&(_, string)| string.name())
+                    .collect();
+                format!(
+                    "{} strings, {} position",
+                    strings.join(" & "),
+                    fingering.position.label()
+                )
+            } else if fingering
+                .notes
+                .iter()
+                .all(|&(_, string)| string == fingering.string)
+            {
+                format!(
+                    "{} string, {} position",
+                    fingering.string.name(),
+                    fingering.position.label()
+                )
+            } else {
+                format!("{} position", fingering.position.label())
+            };
+            svg.text(150, 180, 14, &label);
+            if let Some(pattern) = fingering.pattern {
+                svg.text(150, 195, 10, &format!("finger pattern {}", pattern.name()));
+            } else if let Some(sounding) = self.sounding() {
+                svg.text(150, 195, 10, &format!("sounds {}", sounding));
+            }
+        } else if let (true, Some(harmonic)) = (reveal, self.harmonic) {
+            let sounding = self
+                .sounding()
+                .map_or(String::new(), |note| format!(", sounds {}", note));
+            svg.text(
+                150,
+                180,
+                14,
+                &format!("{} string{}", harmonic.string.name(), sounding),
+            );
+            svg.text(150, 195, 10, &format!("{} harmonic", harmonic.node.label()));
+        } else if let (true, Some(label)) = (reveal, self.reading_answer()) {
+            svg.text(150, 185, 14, &label);
+        }
+        svg
+    }
+
+    /// The pitch a harmonic card sounds: the node's partial for a natural
+    /// harmonic, two octaves above the stopped note for an artificial one.
+    fn sounding(&self) -> Option<Note> {
+        match self.kind {
+            CardKind::NaturalHarmonic => self.harmonic?.sounding(),
+            CardKind::ArtificialHarmonic => {
+                self.notes[0] + Interval::new(IntervalQuality::Perfect, 15).ok()?
+            }
+            _ => None,
+        }
+    }
+
+    /// The answer to cards read without a fingering: the note name, the
+    /// interval, or the scale.
+    fn reading_answer(&self) -> Option<String> {
+        let lowest = *self.notes.iter().min_by_key(|note| note.midi())?;
+        let highest = *self.notes.iter().max_by_key(|note| note.midi())?;
+        match self.kind {
+            CardKind::SingleNote => Some(lowest.to_string()),
+            CardKind::Interval | CardKind::DoubleStop => {
+                Interval::between(lowest, highest).map(|interval| interval.name())
+            }
+            CardKind::ScaleFragment => Some(format!("{} major", lowest.name())),
+            CardKind::Position
+            | CardKind::FullPosition
+            | CardKind::Drill
+            | CardKind::NaturalHarmonic
+            | CardKind::ArtificialHarmonic => None,
+        }
+    }
+
+    /// The accidental drawn next to each note. Accidentals already in the key
+    /// signature are left out, naturals are added where a note contradicts the
+    /// key, and a courtesy accidental is added whenever a note cancels an
+    /// accidental on an earlier note of the same letter.
+    fn displayed_accidentals(&self) -> Vec<Option<Accidental>> {
+        let alteration = |note: &Note| note.accidental.map_or(0, |a| a as i8);
+        let mut shown = vec![None; self.notes.len()];
+        for (i, note) in self.notes.iter().enumerate() {
+            let key_alteration = self.key.alteration(note.letter);
+            let cancels_earlier = self.notes[..i]
+                .iter()
+                .any(|prev| prev.letter == note.letter && alteration(prev) != key_alteration);
+            shown[i] = if alteration(note) != key_alteration
+                || cancels_earlier
+                || note.accidental == Some(Accidental::Natural)
+            {
+                Some(note.accidental.unwrap_or(Accidental::Natural))
+            } else {
+                None
+            };
+        }
+        shown
+    }
+
+    /// Whether a note on the card is spelled with a triple sharp or flat.
+    fn needs_triple_accidental(&self) -> bool {
+        self.notes
+            .iter()
+            .any(|note| note.accidental.is_some_and(Accidental::is_triple))
+    }
+
+    /// Picks the key signature within the allowed range that leaves the fewest
+    /// accidentals on the card, preferring simpler keys on a tie.
+    fn best_key(&self, max_sharps: u8, max_flats: u8) -> KeySignature {
+        let max_sharps = max_sharps.min(7) as i8;
+        let max_flats = max_flats.min(7) as i8;
+        (0..=max_sharps.max(max_flats))
+            .flat_map(|count| [count, -count])
+            .filter(|&fifths| fifths <= max_sharps && -fifths <= max_flats)
+            .map(|fifths| KeySignature { fifths })
+            .min_by_key(|&key| {
+                Card {
+                    key,
+                    ..self.clone()
+                }
+                .displayed_accidentals()
+                .iter()
+                .flatten()
+                .count()
+            })
+            .unwrap_or_default()
+    }
+
+    /// A stable name for the card across decks: the clef, the notes and, when
+    /// known, the string and position they were generated for.
+    pub fn id(&self) -> String {
+        let mut id = self.clef.center_note().to_string();
+        for (i, note) in self.notes.iter().enumerate() {
+            id.push(if i > 0 && self.kind == CardKind::DoubleStop {
+                '+'
+            } else {
+                ':'
+            });
+            id.push_str(&note.to_string());
+        }
+        if let Some(fingering) = &self.fingering {
+            id.push_str(&format!(
+                "/{}/{}",
+                fingering.string.name(),
+                fingering.position.label()
+            ));
+        }
+        if let Some(harmonic) = self.harmonic {
+            id.push_str(&format!("/{}/harmonic", harmonic.string.name()));
+        }
+        id
+    }
+
+    /// The card as a standalone SVG document.
+    pub fn to_svg(&self) -> String {
+        self.svg().to_string()
+    }
+
+    /// Whether the card can be played in `position` with the first finger on
+    /// `string`. The first finger always takes the lowest note, so this holds
+    /// for shuffled cards and accepts any valid reading, not only the one the
+    /// generator picked. The generator's own reading is always accepted, which
+    /// matters for backward extensions. A harmonic only needs the right string.
+    pub fn accepts(&self, string: InstrumentString, position: Position) -> bool {
+        if let Some(harmonic) = self.harmonic {
+            return harmonic.string == string;
+        }
+        let lowest = self.notes.iter().min_by_key(|note| note.midi()).unwrap();
+        string.plays_in(*lowest, position)
+            || self.fingering.as_ref().is_some_and(|fingering| {
+                (fingering.string, fingering.position) == (string, position)
+            })
+    }
+
+    /// The instrument the card was generated for. Cards read from text don't
+    /// say, and are taken to be for cello.
+    pub fn instrument(&self) -> Instrument {
+        self.fingering
+            .as_ref()
+            .map(|fingering| fingering.string)
+            .or(self.harmonic.map(|harmonic| harmonic.string))
+            .map_or(Instrument::Cello, |string| string.instrument())
+    }
+
+    pub fn fingering(&self) -> Option<&Fingering> {
+        self.fingering.as_ref()
+    }
+
+    pub fn kind(&self) -> CardKind {
+        self.kind
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn card_svg_snapshot() {
+    let card: Card = "D3:D3:E3:F3".parse().unwrap();
+    assert_eq!(
+        card.to_svg(),
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 300 200">
+<line x1="0" x2="300" y1="80" y2="80" style="stroke: black; stroke-width: 1;"/>
+<line x1="0" x2="300" y1="90" y2="90" style="stroke: black; stroke-width: 1;"/>
+<line x1="0" x2="300" y1="100" y2="100" style="stroke: black; stroke-width: 1;"/>
+<line x1="0" x2="300" y1="110" y2="110" style="stroke: black; stroke-width: 1;"/>
+<line x1="0" x2="300" y1="120" y2="120" style="stroke: black; stroke-width: 1;"/>
+<path transform="translate(10,80)" d="m 7,7 c 3,0 5,2 5,4 0,2 -2,4 -5,4 C 3,15 1,13 1,9 1,5 5,0 12,0 17,0 25,3 25,12 25,28 0,40 0,39 9,33 19,24 19,12 19,8 18,2 11,2 4,2 3,9 3,9 3,9 4,7 7,7 Z M 28,5 a 2,2 0 1 0 4,0 a 2,2 0 1 0 -4,0 z M 28,15 a 2,2 0 1 0 4,0 a 2,2 0 1 0 -4,0 z" style="fill: #000000; fill-opacity: 1;"/>
+<path d="M 94,101 c 0,2 2,4 4,4 4,0 8,-3 8,-6 0,-3 -2,-4 -4,-4 -4,0 -8,3 -8,6" style="fill: #000000; fill-opacity: 1;"/>
+<line x1="105.5" x2="105.5" y1="99" y2="65" style="stroke: black; stroke-width: 1;"/>
+<path d="M 164,96 c 0,2 2,4 4,4 4,0 8,-3 8,-6 0,-3 -2,-4 -4,-4 -4,0 -8,3 -8,6" style="fill: #000000; fill-opacity: 1;"/>
+<line x1="164.5" x2="164.5" y1="96" y2="130" style="stroke: black; stroke-width: 1;"/>
+<path d="M 234,91 c 0,2 2,4 4,4 4,0 8,-3 8,-6 0,-3 -2,-4 -4,-4 -4,0 -8,3 -8,6" style="fill: #000000; fill-opacity: 1;"/>
+<line x1="234.5" x2="234.5" y1="91" y2="125" style="stroke: black; stroke-width: 1;"/>
+</svg>"#
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn clef_placement() {
+    // every C clef is centered on middle C
+    for (clef, y) in [("G4", 120), ("C4", 100), ("A3", 90), ("F3", 80)] {
+        let card: Card = format!("{clef}:C4:D4:E4").parse().unwrap();
+        let translate = format!(
+            r#"<path transform="translate(10,{})" d="M 0,0 V 40"#,
+            y - 20
+        );
+        assert!(card.to_svg().contains(&translate), "{clef}");
+    }
+    let card: Card = "B3:G3:A3:B3".parse().unwrap();
+    assert!(card.to_svg().contains(">8</text>"));
+}
+
+#[cfg(test)]
+#[test]
+fn courtesy_naturals() {
+    use Accidental::*;
+    let card: Card = "C4:C#4:C4:Cn5".parse().unwrap();
+    assert_eq!(
+        card.displayed_accidentals(),
+        [Some(Sharp), Some(Natural), Some(Natural)]
+    );
+    let card: Card = "C4:Bb3:C4:B4".parse().unwrap();
+    assert_eq!(
+        card.displayed_accidentals(),
+        [Some(Flat), None, Some(Natural)]
+    );
+    let card: Card = "C4:Cn4:D4:E4".parse().unwrap();
+    assert_eq!(card.displayed_accidentals(), [Some(Natural), None, None]);
+    assert!(card.to_svg().contains("m 84,86 h 1 v 22 h -1 z"));
+    assert_eq!(card.id(), "C4:Cn4:D4:E4");
+    assert_ne!(card.id(), "C4:C4:D4:E4".parse::<Card>().unwrap().id());
+}
+
+impl From<&Card> for Element {
+    fn from(card: &Card) -> Self {
+        svg_element(&card.svg())
+    }
+}
+
+/// Builds the DOM element for a card showing its answer.
+pub fn answer_card(card: &Card) -> Element {
+    svg_element(&card.answer_svg())
+}
+
+fn svg_element(svg: &Svg) -> Element {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let card_svg: SvgsvgElement = document
+        .create_element_ns(SVG_NAMESPACE, "svg")
+        .unwrap()
+        .dyn_into()
+        .unwrap();
+    card_svg.set_attribute("viewBox", svg.view_box()).unwrap();
+    card_svg.set_attribute("width", "100%").unwrap();
+    card_svg.set_attribute("height", "100%").unwrap();
+    card_svg.set_inner_html(svg.body());
+    card_svg.dyn_into().unwrap()
+}
+
+/// The first filter that leaves a deck with no cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyDeck {
+    NoCardTypes,
+    NoClefs,
+    /// Every clef's lowest note is above its highest.
+    EmptyClefRanges,
+    /// Only cards played on a string are allowed, and no string is.
+    NoStrings,
+    Accidentals,
+    Positions,
+    FingerPatterns,
+    Scales,
+    /// The clef ranges are too narrow to hold any card.
+    ClefRanges,
+    /// No single filter is to blame, only all of them together.
+    Combination,
+}
+
+impl EmptyDeck {
+    pub fn explanation(self) -> &'static str {
+        match self {
+            EmptyDeck::NoCardTypes => "No card types are switched on.",
+            EmptyDeck::NoClefs => "No clefs are switched on.",
+            EmptyDeck::EmptyClefRanges => "Every clef's lowest note is above its highest note.",
+            EmptyDeck::NoStrings => "No strings are switched on.",
+            EmptyDeck::Accidentals => {
+                "The limits on sharps, flats, double and triple accidentals leave out every card."
+            }
+            EmptyDeck::Positions => "None of the allowed positions can play the notes.",
+            EmptyDeck::FingerPatterns => "None of the allowed finger patterns fit.",
+            EmptyDeck::Scales => "No major, minor or modal scales are switched on.",
+            EmptyDeck::ClefRanges => "The clef ranges are too narrow to fit any card.",
+            EmptyDeck::Combination => "No card fits all of the settings at once.",
+        }
+    }
+}
+
+/// A menu input that can't be turned into settings, named by its element id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingsError {
+    /// A count that isn't a whole number from `min` to `max`.
+    Count {
+        field: &'static str,
+        min: u8,
+        max: u8,
+    },
+    /// A clef range end that is neither a note name nor a MIDI number.
+    Note { field: &'static str },
+    /// A clef range with only one end filled in.
+    MissingNote { field: &'static str },
+    /// A clef range whose lowest note is above its highest.
+    ReversedRange { field: &'static str },
+    /// A card limit that is neither blank nor a whole number above zero.
+    CardLimit,
+    /// A seed that is neither blank nor a whole number.
+    Seed,
+}
+
+impl SettingsError {
+    /// The id of the input the error should be marked next to.
+    pub fn field(&self) -> &'static str {
+        match self {
+            SettingsError::Count { field, .. }
+            | SettingsError::Note { field }
+            | SettingsError::MissingNote { field }
+            | SettingsError::ReversedRange { field } => field,
+            SettingsError::CardLimit => "max_card_count",
+            SettingsError::Seed => "seed",
+        }
+    }
+
+    pub fn explanation(&self) -> String {
+        match self {
+            SettingsError::Count { min, max, .. } => {
+                format!("Use a whole number from {} to {}.", min, max)
+            }
+            SettingsError::Note { .. } => "Use a note like C4 or a MIDI number.".to_string(),
+            SettingsError::MissingNote { .. } => {
+                "Fill in both ends, or leave both blank to turn the clef off.".to_string()
+            }
+            SettingsError::ReversedRange { .. } => {
+                "The lowest note is above the highest.".to_string()
+            }
+            SettingsError::CardLimit => {
+                "Use a whole number above zero, or leave it blank for no limit.".to_string()
+            }
+            SettingsError::Seed => {
+                "Use a whole number, or leave it blank for a random seed.".to_string()
+            }
+        }
+    }
+}
+
+/// A group of cards with the key the generator samples it by: the place in
+/// the schedule of its most pressing card, then a random key.
+struct Ranked {
+    key: ((u8, u32), u64),
+    cards: Vec<Card>,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// Bumped whenever `CardGenerator::to_query` changes meaning, so old links
+/// are rejected rather than misread.
+const QUERY_VERSION: u8 = 11;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardGenerator {
+    instrument: Instrument,
+    /// Which `CardKind`s go in the deck.
+    position_cards_allowed: bool,
+    single_note_cards_allowed: bool,
+    interval_cards_allowed: bool,
+    full_position_cards_allowed: bool,
+    scale_fragment_cards_allowed: bool,
+    scale_cards_allowed: bool,
+    arpeggio_cards_allowed: bool,
+    double_stop_cards_allowed: bool,
+    natural_harmonic_cards_allowed: bool,
+    artificial_harmonic_cards_allowed: bool,
+    /// Print the pitch a harmonic sounds under it on the card.
+    sounding_pitch_shown: bool,
+    /// Which scales the scale and arpeggio cards are drawn from.
+    major_scales_allowed: bool,
+    minor_scales_allowed: bool,
+    modal_scales_allowed: bool,
+    scale_octaves: u8,
+    /// Which of the instrument's finger patterns position cards may use.
+    finger_patterns_allowed: HashSet<FingerPattern>,
+    bass_clef: Option<RangeInclusive<u8>>,
+    tenor_clef: Option<RangeInclusive<u8>>,
+    treble_clef: Option<RangeInclusive<u8>>,
+    alto_clef: Option<RangeInclusive<u8>>,
+    treble_8vb_clef: Option<RangeInclusive<u8>>,
+    soprano_clef: Option<RangeInclusive<u8>>,
+    baritone_clef: Option<RangeInclusive<u8>>,
+    half_position_allowed: bool,
+    position_1_allowed: bool,
+    position_2_allowed: bool,
+    position_3_allowed: bool,
+    position_4_allowed: bool,
+    position_5_allowed: bool,
+    position_6_allowed: bool,
+    position_7_allowed: bool,
+    thumb_position_allowed: bool,
+    /// Strings are numbered from the highest, like `InstrumentString`.
+    string_1_allowed: bool,
+    string_2_allowed: bool,
+    string_3_allowed: bool,
+    string_4_allowed: bool,
+    max_double_accidentals: u8,
+    /// Whether to keep cards that can only be spelled with a triple sharp or
+    /// flat, such as a doubly augmented interval from a sharp.
+    triple_accidentals_allowed: bool,
+    max_sharps: u8,
+    max_flats: u8,
+    key_signatures: bool,
+    shuffled_order: bool,
+    string_count: u8,
+    max_card_count: Option<NonZeroUsize>,
+    quiz_mode: bool,
+    /// Fixes the shuffle, so the same seed and settings always give the same deck.
+    seed: Option<u64>,
+}
+
+impl CardGenerator {
+    pub fn no_sharps_flats() -> Self {
+        CardGenerator {
+            instrument: Instrument::Cello,
+            position_cards_allowed: true,
+            single_note_cards_allowed: false,
+            interval_cards_allowed: false,
+            full_position_cards_allowed: false,
+            scale_fragment_cards_allowed: false,
+            scale_cards_allowed: false,
+            arpeggio_cards_allowed: false,
+            double_stop_cards_allowed: false,
+            natural_harmonic_cards_allowed: false,
+            artificial_harmonic_cards_allowed: false,
+            sounding_pitch_shown: false,
+            major_scales_allowed: true,
+            minor_scales_allowed: true,
+            modal_scales_allowed: false,
+            scale_octaves: 1,
+            finger_patterns_allowed: Instrument::Cello.patterns_named(&[
+                "1",
+                "2",
+                "3 & 4, forward extension",
+            ]),
+            bass_clef: Some(
+                Note {
+                    letter: Letter::C,
+                    octave: 2,
+                    accidental: None,
+                }
+                .midi()..=Note {
+                    letter: Letter::C,
+                    octave: 5,
+                    accidental: None,
+                }
+                .midi(),
+            ),
+            tenor_clef: None,
+            treble_clef: None,
+            alto_clef: None,
+            treble_8vb_clef: None,
+            soprano_clef: None,
+            baritone_clef: None,
+            half_position_allowed: true,
+            position_1_allowed: true,
+            position_2_allowed: true,
+            position_3_allowed: true,
+            position_4_allowed: true,
+            position_5_allowed: true,
+            position_6_allowed: true,
+            position_7_allowed: true,
+            thumb_position_allowed: false,
+            string_1_allowed: true,
+            string_2_allowed: true,
+            string_3_allowed: true,
+            string_4_allowed: true,
+            max_double_accidentals: 0,
+            triple_accidentals_allowed: false,
+            max_sharps: 0,
+            max_flats: 0,
+            key_signatures: false,
+            shuffled_order: false,
+            string_count: 1,
+            max_card_count: NonZeroUsize::new(100),
+            quiz_mode: false,
+            seed: None,
+        }
+    }
+
+    pub fn one_flat() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.max_flats = 1;
+        gen
+    }
+
+    pub fn one_sharp() -> Self {
+        let mut gen = CardGenerator::one_flat();
+        gen.max_sharps = 1;
+        gen
+    }
+
+    pub fn two_flats() -> Self {
+        let mut gen = CardGenerator::one_sharp();
+        gen.max_flats = 2;
+        gen
+    }
+
+    pub fn two_sharps() -> Self {
+        let mut gen = CardGenerator::two_flats();
+        gen.max_sharps = 2;
+        gen
+    }
+
+    pub fn three_flats() -> Self {
+        let mut gen = CardGenerator::two_sharps();
+        gen.max_flats = 3;
+        gen
+    }
+
+    pub fn three_sharps() -> Self {
+        let mut gen = CardGenerator::three_flats();
+        gen.max_sharps = 3;
+        gen
+    }
+
+    pub fn tenor_clef_initial() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.bass_clef = None;
+        gen.tenor_clef = Some(
+            Note {
+                letter: Letter::C,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn tenor_clef_advanced() -> Self {
+        let mut gen = CardGenerator::three_sharps();
+        gen.bass_clef = None;
+        gen.tenor_clef = Some(
+            Note {
+                letter: Letter::C,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn treble_clef_initial() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.bass_clef = None;
+        gen.treble_clef = Some(
+            Note {
+                letter: Letter::G,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn treble_clef_advanced() -> Self {
+        let mut gen = CardGenerator::three_sharps();
+        gen.bass_clef = None;
+        gen.treble_clef = Some(
+            Note {
+                letter: Letter::G,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn advanced() -> Self {
+        let mut gen = CardGenerator::three_sharps();
+        gen.tenor_clef = Some(
+            Note {
+                letter: Letter::C,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen.treble_clef = Some(
+            Note {
+                letter: Letter::G,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::C,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen.max_double_accidentals = 1;
+        gen.shuffled_order = true;
+        gen.string_count = 2;
+        gen.finger_patterns_allowed.extend(
+            Instrument::Cello
+                .patterns_named(&["5, forward extension", "3 & 4, backward extension"]),
+        );
+        gen
+    }
+
+    /// Two-octave scales and arpeggios in up to three sharps or flats.
+    pub fn scales() -> Self {
+        let mut gen = CardGenerator::three_sharps();
+        gen.position_cards_allowed = false;
+        gen.scale_cards_allowed = true;
+        gen.arpeggio_cards_allowed = true;
+        gen.max_flats = 3;
+        gen.scale_octaves = 2;
+        gen.key_signatures = true;
+        gen.tenor_clef = Some(
+            Note {
+                letter: Letter::D,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::A,
+                octave: 4,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    /// Thumb position only, on the A and D strings, in tenor and treble clef.
+    pub fn thumb_position() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.bass_clef = None;
+        gen.tenor_clef = Some(
+            Note {
+                letter: Letter::D,
+                octave: 4,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::A,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen.treble_clef = Some(
+            Note {
+                letter: Letter::A,
+                octave: 4,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::E,
+                octave: 6,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen.half_position_allowed = false;
+        gen.position_1_allowed = false;
+        gen.position_2_allowed = false;
+        gen.position_3_allowed = false;
+        gen.position_4_allowed = false;
+        gen.position_5_allowed = false;
+        gen.position_6_allowed = false;
+        gen.position_7_allowed = false;
+        gen.thumb_position_allowed = true;
+        gen.string_3_allowed = false;
+        gen.string_4_allowed = false;
+        gen
+    }
+
+    pub fn thumb_position_advanced() -> Self {
+        let mut gen = CardGenerator::thumb_position();
+        gen.position_5_allowed = true;
+        gen.position_6_allowed = true;
+        gen.position_7_allowed = true;
+        gen.string_3_allowed = true;
+        gen.max_sharps = 3;
+        gen.max_flats = 3;
+        gen.max_double_accidentals = 1;
+        gen.shuffled_order = true;
+        gen.string_count = 2;
+        gen
+    }
+
+    pub fn violin() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.instrument = Instrument::Violin;
+        gen.finger_patterns_allowed =
+            Instrument::Violin.patterns_named(&["low 2", "high 2", "high 3"]);
+        gen.bass_clef = None;
+        gen.treble_clef = Some(
+            Note {
+                letter: Letter::G,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::B,
+                octave: 6,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn viola() -> Self {
+        let mut gen = CardGenerator::no_sharps_flats();
+        gen.instrument = Instrument::Viola;
+        gen.finger_patterns_allowed =
+            Instrument::Viola.patterns_named(&["low 2", "high 2", "high 3"]);
+        gen.bass_clef = None;
+        gen.alto_clef = Some(
+            Note {
+                letter: Letter::C,
+                octave: 3,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::B,
+                octave: 5,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn double_bass() -> Self {
+        // a semitone between each finger always needs an accidental
+        let mut gen = CardGenerator::one_sharp();
+        gen.instrument = Instrument::Bass;
+        gen.finger_patterns_allowed = Instrument::Bass.finger_patterns().iter().copied().collect();
+        gen.bass_clef = Some(
+            Note {
+                letter: Letter::E,
+                octave: 2,
+                accidental: None,
+            }
+            .midi()..=Note {
+                letter: Letter::G,
+                octave: 4,
+                accidental: None,
+            }
+            .midi(),
+        );
+        gen
+    }
+
+    pub fn impossible() -> Self {
+        CardGenerator {
+            instrument: Instrument::Cello,
+            position_cards_allowed: true,
+            single_note_cards_allowed: true,
+            interval_cards_allowed: true,
+            full_position_cards_allowed: true,
+            scale_fragment_cards_allowed: true,
+            scale_cards_allowed: true,
+            arpeggio_cards_allowed: true,
+            double_stop_cards_allowed: true,
+            natural_harmonic_cards_allowed: true,
+            artificial_harmonic_cards_allowed: true,
+            sounding_pitch_shown: true,
+            major_scales_allowed: true,
+            minor_scales_allowed: true,
+            modal_scales_allowed: true,
+            scale_octaves: 4,
+            finger_patterns_allowed: Instrument::Cello
+                .finger_patterns()
+                .iter()
+                .copied()
+                .collect(),
+            bass_clef: Some(
+                Note {
+                    letter: Letter::C,
+                    octave: 2,
+                    accidental: None,
+                }
+                .midi()..=Note {
+                    letter: Letter::C,
+                    octave: 5,
+                    accidental: None,
+                }
+                .midi(),
+            ),
+            tenor_clef: Some(
+                Note {
+                    letter: Letter::G,
+                    octave: 3,
+                    accidental: None,
+                }
+                .midi()..=Note {
+                    letter: Letter::C,
+                    octave: 5,
+                    accidental: None,
+                }
+                .midi(),
+            ),
+            treble_clef: Some(
+                Note {
+                    letter: Letter::G,
+                    octave: 3,
+                    accidental: None,
+                }
+                .midi()..=Note {
+                    letter: Letter::C,
+                    octave: 6,
+                    accidental: None,
+                }
+                .midi(),
+            ),
+            alto_clef: Some(
+                Note {
+                    letter: Letter::C,
+                    octave: 3,
+                    accidental: None,
+                }
+                .midi()..=Note {
+                    letter: Letter::G,
+                    octave: 4,
+                    accidental: None,
+                }
+                .midi(),
+            ),
+            treble_8vb_clef: None,
+            soprano_clef: None,
+            baritone_clef: None,
+            half_position_allowed: true,
+            position_1_allowed: true,
+            position_2_allowed: true,
+            position_3_allowed: true,
+            position_4_allowed: true,
+            position_5_allowed: true,
+            position_6_allowed: true,
+            position_7_allowed: true,
+            thumb_position_allowed: true,
+            string_1_allowed: true,
+            string_2_allowed: true,
+            string_3_allowed: true,
+            string_4_allowed: true,
+            max_double_accidentals: 3,
+            triple_accidentals_allowed: true,
+            max_sharps: 3,
+            max_flats: 3,
+            key_signatures: false,
+            shuffled_order: true,
+            string_count: 4,
+            max_card_count: None,
+            quiz_mode: false,
+            seed: None,
+        }
+    }
+
+    /// Reads the settings from the menu, or everything wrong with them.
+    pub fn read_settings() -> Result<CardGenerator, Vec<SettingsError>> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let element = |element_id: &str| {
+            document
+                .get_element_by_id(element_id)
+                .unwrap()
+                .dyn_into::<HtmlInputElement>()
+                .unwrap()
+        };
+        let instrument = document
+            .get_element_by_id("instrument")
+            .unwrap()
+            .dyn_into::<HtmlSelectElement>()
+            .unwrap()
+            .value();
+        CardGenerator::from_form(
+            &instrument,
+            |id| element(id).value(),
+            |id| element(id).checked(),
+        )
+    }
+
+    /// Builds settings from the menu's inputs, given each text input's value
+    /// and each checkbox's state by element id. Every problem is collected,
+    /// so they can all be marked at once.
+    pub fn from_form(
+        instrument: &str,
+        value: impl Fn(&str) -> String,
+        checked: impl Fn(&str) -> bool,
+    ) -> Result<CardGenerator, Vec<SettingsError>> {
+        let mut errors = Vec::new();
+        let count =
+            |errors: &mut Vec<SettingsError>, field: &'static str, min: u8, max: u8| match value(
+                field,
+            )
+            .trim()
+            .parse()
+            {
+                Ok(count) if (min..=max).contains(&count) => count,
+                _ => {
+                    errors.push(SettingsError::Count { field, min, max });
+                    min
+                }
+            };
+        let instrument = instrument.parse().unwrap_or(Instrument::Cello);
+        let position_cards_allowed = checked("position_cards_allowed");
+        let single_note_cards_allowed = checked("single_note_cards_allowed");
+        let interval_cards_allowed = checked("interval_cards_allowed");
+        let full_position_cards_allowed = checked("full_position_cards_allowed");
+        let scale_fragment_cards_allowed = checked("scale_fragment_cards_allowed");
+        let scale_cards_allowed = checked("scale_cards_allowed");
+        let arpeggio_cards_allowed = checked("arpeggio_cards_allowed");
+        let double_stop_cards_allowed = checked("double_stop_cards_allowed");
+        let natural_harmonic_cards_allowed = checked("natural_harmonic_cards_allowed");
+        let artificial_harmonic_cards_allowed = checked("artificial_harmonic_cards_allowed");
+        let sounding_pitch_shown = checked("sounding_pitch_shown");
+        let major_scales_allowed = checked("major_scales_allowed");
+        let minor_scales_allowed = checked("minor_scales_allowed");
+        let modal_scales_allowed = checked("modal_scales_allowed");
+        let scale_octaves = count(&mut errors, "scale_octaves", 1, 4);
+        // the menu has a switch for each of the instrument's patterns, in order
+        let finger_patterns_allowed = instrument
+            .finger_patterns()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| checked(&format!("finger_pattern_{}_allowed", index + 1)))
+            .map(|(_, &pattern)| pattern)
+            .collect();
+
+        // Both ends blank switches the clef off.
+        let note = |errors: &mut Vec<SettingsError>, field: &'static str| {
+            let text = value(field);
+            let text = text.trim();
+            let midi = text
+                .parse::<u8>()
+                .ok()
+                .filter(|&midi| midi <= 127)
+                .or(text.parse::<Note>().ok().map(|n| n.midi()));
+            if midi.is_none() && !text.is_empty() {
+                errors.push(SettingsError::Note { field });
+            }
+            (text.is_empty(), midi)
+        };
+        macro_rules! range {
+            ($id:literal) => {{
+                let min = note(&mut errors, concat!($id, "_clef_min"));
+                let max = note(&mut errors, concat!($id, "_clef_max"));
+                match (min, max) {
+                    ((true, _), (true, _)) => None,
+                    ((true, _), _) => {
+                        errors.push(SettingsError::MissingNote {
+                            field: concat!($id, "_clef_min"),
+                        });
+                        None
+                    }
+                    (_, (true, _)) => {
+                        errors.push(SettingsError::MissingNote {
+                            field: concat!($id, "_clef_max"),
+                        });
+                        None
+                    }
+                    ((_, Some(min)), (_, Some(max))) if min > max => {
+                        errors.push(SettingsError::ReversedRange {
+                            field: concat!($id, "_clef_min"),
+                        });
+                        None
+                    }
+                    ((_, min), (_, max)) => min.zip(max).map(|(min, max)| min..=max),
+                }
+            }};
+        }
+        let bass_clef = range!("bass");
+        let tenor_clef = range!("tenor");
+        let treble_clef = range!("treble");
+        let alto_clef = range!("alto");
+        let treble_8vb_clef = range!("treble_8vb");
+        let soprano_clef = range!("soprano");
+        let baritone_clef = range!("baritone");
+
+        let half_position_allowed = checked("half_position_allowed");
+        let position_1_allowed = checked("position_1_allowed");
+        let position_2_allowed = checked("position_2_allowed");
+        let position_3_allowed = checked("position_3_allowed");
+        let position_4_allowed = checked("position_4_allowed");
+        let position_5_allowed = checked("position_5_allowed");
+        let position_6_allowed = checked("position_6_allowed");
+        let position_7_allowed = checked("position_7_allowed");
+        let thumb_position_allowed = checked("thumb_position_allowed");
+
+        let string_1_allowed = checked("string_1_allowed");
+        let string_2_allowed = checked("string_2_allowed");
+        let string_3_allowed = checked("string_3_allowed");
+        let string_4_allowed = checked("string_4_allowed");
+        let max_double_accidentals = count(&mut errors, "max_double_accidentals", 0, 7);
+        let triple_accidentals_allowed = checked("triple_accidentals_allowed");
+        let max_sharps = count(&mut errors, "max_sharps", 0, 7);
+        let max_flats = count(&mut errors, "max_flats", 0, 7);
+        let key_signatures = checked("key_signatures");
+
+        let shuffled_order = checked("shuffled_order");
+        let string_count = count(&mut errors, "string_count", 1, 4);
+        let max_card_count = match value("max_card_count").trim() {
+            "" => None,
+            limit => limit
+                .parse::<NonZeroUsize>()
+                .map_err(|_| errors.push(SettingsError::CardLimit))
+                .ok(),
+        };
+        let quiz_mode = checked("quiz_mode");
+        let seed = match value("seed").trim() {
+            "" => None,
+            seed => seed
+                .parse()
+                .map_err(|_| errors.push(SettingsError::Seed))
+                .ok(),
+        };
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(CardGenerator {
+            instrument,
+            position_cards_allowed,
+            single_note_cards_allowed,
+            interval_cards_allowed,
+            full_position_cards_allowed,
+            scale_fragment_cards_allowed,
+            scale_cards_allowed,
+            arpeggio_cards_allowed,
+            double_stop_cards_allowed,
+            natural_harmonic_cards_allowed,
+            artificial_harmonic_cards_allowed,
+            sounding_pitch_shown,
+            major_scales_allowed,
+            minor_scales_allowed,
+            modal_scales_allowed,
+            scale_octaves,
+            finger_patterns_allowed,
+            bass_clef,
+            tenor_clef,
+            treble_clef,
+            alto_clef,
+            treble_8vb_clef,
+            soprano_clef,
+            baritone_clef,
+            half_position_allowed,
+            position_1_allowed,
+            position_2_allowed,
+            position_3_allowed,
+            position_4_allowed,
+            position_5_allowed,
+            position_6_allowed,
+            position_7_allowed,
+            thumb_position_allowed,
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+            max_double_accidentals,
+            triple_accidentals_allowed,
+            max_sharps,
+            max_flats,
+            key_signatures,
+            shuffled_order,
+            string_count,
+            max_card_count,
+            quiz_mode,
+            seed,
+        })
+    }
+
+    pub fn write_settings(&self) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let element = |element_id: &str| {
+            document
+                .get_element_by_id(element_id)
+                .unwrap()
+                .dyn_into::<HtmlInputElement>()
+                .unwrap()
+        };
+        document
+            .get_element_by_id("instrument")
+            .unwrap()
+            .dyn_into::<HtmlSelectElement>()
+            .unwrap()
+            .set_value(self.instrument.id());
+        crate::update_instrument_labels();
+        self.fill_form(
+            |id, value| element(id).set_value(&value),
+            |id, checked| element(id).set_checked(checked),
+        );
+        if self.bass_clef.is_some() {
+            crate::update_bass_notes();
+        }
+        if self.tenor_clef.is_some() {
+            crate::update_tenor_notes();
+        }
+        if self.treble_clef.is_some() {
+            crate::update_treble_notes();
+        }
+        if self.alto_clef.is_some() {
+            crate::update_alto_notes();
+        }
+        if self.treble_8vb_clef.is_some() {
+            crate::update_treble_8vb_notes();
+        }
+        if self.soprano_clef.is_some() {
+            crate::update_soprano_notes();
+        }
+        if self.baritone_clef.is_some() {
+            crate::update_baritone_notes();
+        }
+    }
+
+    /// Everything `from_form` would reject in these settings, such as a
+    /// reversed clef range from a hand-edited link.
+    pub fn check(&self) -> Result<(), Vec<SettingsError>> {
+        self.through_form().map(|_| ())
+    }
+
+    /// These settings written to a form and read back.
+    fn through_form(&self) -> Result<CardGenerator, Vec<SettingsError>> {
+        let mut values = HashMap::new();
+        let mut checks = HashMap::new();
+        self.fill_form(
+            |id, value| {
+                values.insert(id.to_string(), value);
+            },
+            |id, checked| {
+                checks.insert(id.to_string(), checked);
+            },
+        );
+        CardGenerator::from_form(
+            self.instrument.id(),
+            |id| values.get(id).cloned().unwrap_or_default(),
+            |id| checks.get(id).copied().unwrap_or_default(),
+        )
+    }
+
+    /// Sets the menu's inputs, other than the instrument, to these settings:
+    /// the inverse of `from_form`.
+    pub fn fill_form(
+        &self,
+        mut value: impl FnMut(&str, String),
+        mut checked: impl FnMut(&str, bool),
+    ) {
+        let CardGenerator {
+            instrument,
+            position_cards_allowed,
+            single_note_cards_allowed,
+            interval_cards_allowed,
+            full_position_cards_allowed,
+            scale_fragment_cards_allowed,
+            scale_cards_allowed,
+            arpeggio_cards_allowed,
+            double_stop_cards_allowed,
+            natural_harmonic_cards_allowed,
+            artificial_harmonic_cards_allowed,
+            sounding_pitch_shown,
+            major_scales_allowed,
+            minor_scales_allowed,
+            modal_scales_allowed,
+            scale_octaves,
+            finger_patterns_allowed,
+            bass_clef,
+            tenor_clef,
+            treble_clef,
+            alto_clef,
+            treble_8vb_clef,
+            soprano_clef,
+            baritone_clef,
+            half_position_allowed,
+            position_1_allowed,
+            position_2_allowed,
+            position_3_allowed,
+            position_4_allowed,
+            position_5_allowed,
+            position_6_allowed,
+            position_7_allowed,
+            thumb_position_allowed,
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+            max_double_accidentals,
+            triple_accidentals_allowed,
+            max_sharps,
+            max_flats,
+            key_signatures,
+            shuffled_order,
+            string_count,
+            max_card_count,
+            quiz_mode,
+            seed,
+        } = self.clone();
+        // a range from a shared link can reach past the notes MIDI can name
+        let note_name =
+            |midi: u8| Note::from_midi(midi).map_or(midi.to_string(), |note| note.to_string());
+        checked("position_cards_allowed", position_cards_allowed);
+        checked("single_note_cards_allowed", single_note_cards_allowed);
+        checked("interval_cards_allowed", interval_cards_allowed);
+        checked("full_position_cards_allowed", full_position_cards_allowed);
+        checked("scale_fragment_cards_allowed", scale_fragment_cards_allowed);
+        checked("scale_cards_allowed", scale_cards_allowed);
+        checked("arpeggio_cards_allowed", arpeggio_cards_allowed);
+        checked("double_stop_cards_allowed", double_stop_cards_allowed);
+        checked(
+            "natural_harmonic_cards_allowed",
+            natural_harmonic_cards_allowed,
+        );
+        checked(
+            "artificial_harmonic_cards_allowed",
+            artificial_harmonic_cards_allowed,
+        );
+        checked("sounding_pitch_shown", sounding_pitch_shown);
+        checked("major_scales_allowed", major_scales_allowed);
+        checked("minor_scales_allowed", minor_scales_allowed);
+        checked("modal_scales_allowed", modal_scales_allowed);
+        value("scale_octaves", scale_octaves.to_string());
+        let patterns = instrument.finger_patterns();
+        for number in 1..=5 {
+            let allowed = patterns
+                .get(number - 1)
+                .is_some_and(|pattern| finger_patterns_allowed.contains(pattern));
+            checked(&format!("finger_pattern_{}_allowed", number), allowed);
+        }
+
+        for (clef, range) in [
+            ("bass", bass_clef),
+            ("tenor", tenor_clef),
+            ("treble", treble_clef),
+            ("alto", alto_clef),
+            ("treble_8vb", treble_8vb_clef),
+            ("soprano", soprano_clef),
+            ("baritone", baritone_clef),
+        ] {
+            let (min, max) = range.map_or((String::new(), String::new()), |range| {
+                (note_name(*range.start()), note_name(*range.end()))
+            });
+            value(&format!("{}_clef_min", clef), min);
+            value(&format!("{}_clef_max", clef), max);
+        }
+
+        checked("half_position_allowed", half_position_allowed);
+        checked("position_1_allowed", position_1_allowed);
+        checked("position_2_allowed", position_2_allowed);
+        checked("position_3_allowed", position_3_allowed);
+        checked("position_4_allowed", position_4_allowed);
+        checked("position_5_allowed", position_5_allowed);
+        checked("position_6_allowed", position_6_allowed);
+        checked("position_7_allowed", position_7_allowed);
+        checked("thumb_position_allowed", thumb_position_allowed);
+
+        checked("string_1_allowed", string_1_allowed);
+        checked("string_2_allowed", string_2_allowed);
+        checked("string_3_allowed", string_3_allowed);
+        checked("string_4_allowed", string_4_allowed);
+        value("max_double_accidentals", max_double_accidentals.to_string());
+        checked("triple_accidentals_allowed", triple_accidentals_allowed);
+        value("max_sharps", max_sharps.to_string());
+        value("max_flats", max_flats.to_string());
+        checked("key_signatures", key_signatures);
+
+        checked("shuffled_order", shuffled_order);
+        value("string_count", string_count.to_string());
+
+        match max_card_count {
+            Some(x) => value("max_card_count", x.to_string()),
+            None => value("max_card_count", String::new()),
+        }
+        checked("quiz_mode", quiz_mode);
+        value("seed", seed.map_or(String::new(), |seed| seed.to_string()));
+    }
+
+    pub fn instrument(&self) -> Instrument {
+        self.instrument
+    }
+
+    pub fn quiz_mode(&self) -> bool {
+        self.quiz_mode
+    }
+
+    /// The seed to shuffle the next deck with. Without one a random seed is
+    /// picked and kept, so that deck can still be shown and shared afterwards.
+    pub fn pin_seed(&mut self) -> u64 {
+        *self.seed.get_or_insert_with(|| rand::thread_rng().gen())
+    }
+
+    /// Encodes every setting as a URL query string (without the leading `?`),
+    /// so a deck can be shared as a link. Switches are packed into strings of
+    /// `0`s and `1`s and clef ranges are written as MIDI numbers, e.g.
+    /// `v=10&instrument=cello&kinds=1000000000&sounding=0&scales=110&octaves=1&fingers=11100&bass=36-72&tenor=&...`.
+    pub fn to_query(&self) -> String {
+        let CardGenerator {
+            instrument,
+            position_cards_allowed,
+            single_note_cards_allowed,
+            interval_cards_allowed,
+            full_position_cards_allowed,
+            scale_fragment_cards_allowed,
+            scale_cards_allowed,
+            arpeggio_cards_allowed,
+            double_stop_cards_allowed,
+            natural_harmonic_cards_allowed,
+            artificial_harmonic_cards_allowed,
+            sounding_pitch_shown,
+            major_scales_allowed,
+            minor_scales_allowed,
+            modal_scales_allowed,
+            scale_octaves,
+            finger_patterns_allowed,
+            bass_clef,
+            tenor_clef,
+            treble_clef,
+            alto_clef,
+            treble_8vb_clef,
+            soprano_clef,
+            baritone_clef,
+            half_position_allowed,
+            position_1_allowed,
+            position_2_allowed,
+            position_3_allowed,
+            position_4_allowed,
+            position_5_allowed,
+            position_6_allowed,
+            position_7_allowed,
+            thumb_position_allowed,
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+            max_double_accidentals,
+            triple_accidentals_allowed,
+            max_sharps,
+            max_flats,
+            key_signatures,
+            shuffled_order,
+            string_count,
+            max_card_count,
+            quiz_mode,
+            seed,
+        } = self.clone();
+        let bits = |flags: &[bool]| {
+            flags
+                .iter()
+                .map(|&flag| if flag { '1' } else { '0' })
+                .collect::<String>()
+        };
+        let range = |range: Option<RangeInclusive<u8>>| {
+            range.map_or(String::new(), |range| {
+                format!("{}-{}", range.start(), range.end())
+            })
+        };
+        [
+            ("v", QUERY_VERSION.to_string()),
+            ("instrument", instrument.id().to_string()),
+            (
+                "kinds",
+                bits(&[
+                    position_cards_allowed,
+                    single_note_cards_allowed,
+                    interval_cards_allowed,
+                    full_position_cards_allowed,
+                    scale_fragment_cards_allowed,
+                    scale_cards_allowed,
+                    arpeggio_cards_allowed,
+                    double_stop_cards_allowed,
+                    natural_harmonic_cards_allowed,
+                    artificial_harmonic_cards_allowed,
+                ]),
+            ),
+            ("sounding", bits(&[sounding_pitch_shown])),
+            (
+                "scales",
+                bits(&[
+                    major_scales_allowed,
+                    minor_scales_allowed,
+                    modal_scales_allowed,
+                ]),
+            ),
+            ("octaves", scale_octaves.to_string()),
+            (
+                "fingers",
+                instrument
+                    .finger_patterns()
+                    .iter()
+                    .filter(|pattern| finger_patterns_allowed.contains(pattern))
+                    .map(|pattern| pattern.key())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            ("bass", range(bass_clef)),
+            ("tenor", range(tenor_clef)),
+            ("treble", range(treble_clef)),
+            ("alto", range(alto_clef)),
+            ("treble8vb", range(treble_8vb_clef)),
+            ("soprano", range(soprano_clef)),
+            ("baritone", range(baritone_clef)),
+            (
+                "positions",
+                bits(&[
+                    half_position_allowed,
+                    position_1_allowed,
+                    position_2_allowed,
+                    position_3_allowed,
+                    position_4_allowed,
+                    position_5_allowed,
+                    position_6_allowed,
+                    position_7_allowed,
+                    thumb_position_allowed,
+                ]),
+            ),
+            (
+                "strings",
+                bits(&[
+                    string_1_allowed,
+                    string_2_allowed,
+                    string_3_allowed,
+                    string_4_allowed,
+                ]),
+            ),
+            ("doubles", max_double_accidentals.to_string()),
+            ("triples", bits(&[triple_accidentals_allowed])),
+            ("sharps", max_sharps.to_string()),
+            ("flats", max_flats.to_string()),
+            ("keys", bits(&[key_signatures])),
+            ("shuffled", bits(&[shuffled_order])),
+            ("span", string_count.to_string()),
+            (
+                "count",
+                max_card_count.map_or(String::new(), |count| count.to_string()),
+            ),
+            ("quiz", bits(&[quiz_mode])),
+            ("seed", seed.map_or(String::new(), |seed| seed.to_string())),
+        ]
+        .map(|(key, value)| format!("{}={}", key, value))
+        .join("&")
+    }
+
+    /// Reads settings written by `to_query`. A leading `?` is skipped, unknown
+    /// keys are ignored, and a missing key, a bad value, settings the menu
+    /// would reject or a different version rejects the whole query. The seed alone is optional, as links without
+    /// one just ask for a fresh deck.
+    #[allow(clippy::result_unit_err)]
+    pub fn from_query(query: &str) -> Result<CardGenerator, ()> {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        let value = |key: &str| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|&(name, _)| name == key)
+                .map(|(_, value)| value)
+                .ok_or(())
+        };
+        fn bits<const N: usize>(value: &str) -> Result<[bool; N], ()> {
+            let bytes: [u8; N] = value.as_bytes().try_into().map_err(|_| ())?;
+            let mut flags = [false; N];
+            for (flag, byte) in flags.iter_mut().zip(bytes) {
+                *flag = match byte {
+                    b'0' => false,
+                    b'1' => true,
+                    _ => return Err(()),
+                };
+            }
+            Ok(flags)
+        }
+        let range = |key| -> Result<_, ()> {
+            match value(key)? {
+                "" => Ok(None),
+                range => {
+                    let (min, max) = range.split_once('-').ok_or(())?;
+                    Ok(Some(
+                        min.parse().map_err(|_| ())?..=max.parse().map_err(|_| ())?,
+                    ))
+                }
+            }
+        };
+        let number = |key| value(key)?.parse().map_err(|_| ());
+
+        if value("v")? != QUERY_VERSION.to_string() {
+            return Err(());
+        }
+        let instrument: Instrument = value("instrument")?.parse()?;
+        let [position_cards_allowed, single_note_cards_allowed, interval_cards_allowed, full_position_cards_allowed, scale_fragment_cards_allowed, scale_cards_allowed, arpeggio_cards_allowed, double_stop_cards_allowed, natural_harmonic_cards_allowed, artificial_harmonic_cards_allowed] =
+            bits(value("kinds")?)?;
+        let [sounding_pitch_shown] = bits(value("sounding")?)?;
+        let [major_scales_allowed, minor_scales_allowed, modal_scales_allowed] =
+            bits(value("scales")?)?;
+        let finger_patterns_allowed = value("fingers")?
+            .split(',')
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                instrument
+                    .finger_patterns()
+                    .iter()
+                    .find(|pattern| pattern.key() == key)
+                    .copied()
+                    .ok_or(())
+            })
+            .collect::<Result<_, ()>>()?;
+        let [half_position_allowed, position_1_allowed, position_2_allowed, position_3_allowed, position_4_allowed, position_5_allowed, position_6_allowed, position_7_allowed, thumb_position_allowed] =
+            bits(value("positions")?)?;
+        let [string_1_allowed, string_2_allowed, string_3_allowed, string_4_allowed] =
+            bits(value("strings")?)?;
+        let [triple_accidentals_allowed] = bits(value("triples")?)?;
+        let [key_signatures] = bits(value("keys")?)?;
+        let [shuffled_order] = bits(value("shuffled")?)?;
+        let [quiz_mode] = bits(value("quiz")?)?;
+        let max_card_count = match value("count")? {
+            "" => None,
+            count => Some(count.parse().map_err(|_| ())?),
+        };
+        let seed = match value("seed").unwrap_or_default() {
+            "" => None,
+            seed => Some(seed.parse().map_err(|_| ())?),
+        };
+        let settings = CardGenerator {
+            instrument,
+            position_cards_allowed,
+            single_note_cards_allowed,
+            interval_cards_allowed,
+            full_position_cards_allowed,
+            scale_fragment_cards_allowed,
+            scale_cards_allowed,
+            arpeggio_cards_allowed,
+            double_stop_cards_allowed,
+            natural_harmonic_cards_allowed,
+            artificial_harmonic_cards_allowed,
+            sounding_pitch_shown,
+            major_scales_allowed,
+            minor_scales_allowed,
+            modal_scales_allowed,
+            scale_octaves: number("octaves")?,
+            finger_patterns_allowed,
+            bass_clef: range("bass")?,
+            tenor_clef: range("tenor")?,
+            treble_clef: range("treble")?,
+            alto_clef: range("alto")?,
+            treble_8vb_clef: range("treble8vb")?,
+            soprano_clef: range("soprano")?,
+            baritone_clef: range("baritone")?,
+            half_position_allowed,
+            position_1_allowed,
+            position_2_allowed,
+            position_3_allowed,
+            position_4_allowed,
+            position_5_allowed,
+            position_6_allowed,
+            position_7_allowed,
+            thumb_position_allowed,
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+            max_double_accidentals: number("doubles")?,
+            triple_accidentals_allowed,
+            max_sharps: number("sharps")?,
+            max_flats: number("flats")?,
+            key_signatures,
+            shuffled_order,
+            string_count: number("span")?,
+            max_card_count,
+            quiz_mode,
+            seed,
+        };
+        settings.check().map_err(|_| ())?;
+        Ok(settings)
+    }
+
+    /// Builds a shuffled deck. Cards the schedule has due come first, so they
+    /// are never the ones left out when the deck is cut down to
+    /// `max_card_count`. A drill is ranked by its most pressing part and is
+    /// dealt or left out whole, so a drill longer than the limit never makes
+    /// the cut. Cards are kept only while they might make the cut, so a short
+    /// deck from wide settings never holds every card at once.
+    pub fn card_generator<R: Rng + ?Sized>(&self, rng: &mut R, schedule: &Schedule) -> Vec<Card> {
+        let limit = self.max_card_count.map_or(usize::MAX, NonZeroUsize::get);
+        // reservoir sampling by random keys: shuffling and then sorting by the
+        // schedule keeps the groups with the smallest keys that fit
+        let mut reservoir: BinaryHeap<Ranked> = BinaryHeap::new();
+        let mut kept = 0;
+        for group in self.card_groups(ChaCha8Rng::seed_from_u64(rng.gen())) {
+            let shuffle: u64 = rng.gen();
+            let Some(rank) = group.iter().map(|card| schedule.rank(card)).min() else {
+                continue;
+            };
+            kept += group.len();
+            reservoir.push(Ranked {
+                key: (rank, shuffle),
+                cards: group,
+            });
+            while kept > limit {
+                let largest = reservoir.pop().expect("cards are kept, so some group is");
+                kept -= largest.cards.len();
+            }
+        }
+        reservoir
+            .into_sorted_vec()
+            .into_iter()
+            .flat_map(|ranked| ranked.cards)
+            .collect()
+    }
+
+    /// A deck from the same set of cards as `card_generator`, built the way it
+    /// used to be: every card collected, shuffled, ordered and only then cut
+    /// down. It draws from the rng in a different order, so the same seed
+    /// deals a different deck. Kept to compare against in the generator
+    /// benchmark.
+    #[doc(hidden)]
+    pub fn collected_card_generator<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        schedule: &Schedule,
+    ) -> Vec<Card> {
+        let mut groups: Vec<Vec<Card>> = self
+            .card_groups(ChaCha8Rng::seed_from_u64(rng.gen()))
+            .collect();
+        // drills are shuffled and ordered in whole, so their parts stay in order
+        groups.shuffle(rng);
+        groups.sort_by_cached_key(|group| group.iter().map(|card| schedule.rank(card)).min());
+        let limit = self.max_card_count.map_or(usize::MAX, NonZeroUsize::get);
+        let mut kept = 0;
+        groups
+            .into_iter()
+            .take_while(|group| {
+                kept += group.len();
+                kept <= limit
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// How many cards the settings allow before the deck is cut down to
+    /// `max_card_count`, counted without building the deck.
+    pub fn card_count<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        self.card_groups(ChaCha8Rng::seed_from_u64(rng.gen()))
+            .map(|group| group.len())
+            .sum()
+    }
+
+    /// Why the settings make no cards, or `None` if they make some. Switches
+    /// that leave nothing to generate are checked first, then each filter is
+    /// loosened in turn to find the one that lets no card through.
+    pub fn empty_deck_reason(&self) -> Option<EmptyDeck> {
+        if self.has_cards() {
+            return None;
+        }
+        let reading = self.single_note_cards_allowed
+            || self.interval_cards_allowed
+            || self.scale_fragment_cards_allowed;
+        let drills = self.scale_cards_allowed || self.arpeggio_cards_allowed;
+        let played = self.position_cards_allowed
+            || self.full_position_cards_allowed
+            || drills
+            || self.double_stop_cards_allowed
+            || self.natural_harmonic_cards_allowed
+            || self.artificial_harmonic_cards_allowed;
+        if !reading && !played {
+            return Some(EmptyDeck::NoCardTypes);
+        }
+        let clefs = [
+            &self.bass_clef,
+            &self.treble_clef,
+            &self.tenor_clef,
+            &self.alto_clef,
+            &self.treble_8vb_clef,
+            &self.soprano_clef,
+            &self.baritone_clef,
+        ];
+        if clefs.iter().all(|range| range.is_none()) {
+            return Some(EmptyDeck::NoClefs);
+        }
+        if clefs
+            .iter()
+            .copied()
+            .flatten()
+            .all(|range| range.is_empty())
+        {
+            return Some(EmptyDeck::EmptyClefRanges);
+        }
+        if !reading
+            && ![
+                self.string_1_allowed,
+                self.string_2_allowed,
+                self.string_3_allowed,
+                self.string_4_allowed,
+            ]
+            .contains(&true)
+        {
+            return Some(EmptyDeck::NoStrings);
+        }
+
+        let widen = |range: &Option<RangeInclusive<u8>>| range.as_ref().map(|_| 0..=127);
+        let loosened = [
+            (
+                EmptyDeck::Accidentals,
+                CardGenerator {
+                    max_sharps: 7,
+                    max_flats: 7,
+                    max_double_accidentals: 7,
+                    triple_accidentals_allowed: true,
+                    ..self.clone()
+                },
+            ),
+            (
+                EmptyDeck::Positions,
+                CardGenerator {
+                    half_position_allowed: true,
+                    position_1_allowed: true,
+                    position_2_allowed: true,
+                    position_3_allowed: true,
+                    position_4_allowed: true,
+                    position_5_allowed: true,
+                    position_6_allowed: true,
+                    position_7_allowed: true,
+                    thumb_position_allowed: true,
+                    ..self.clone()
+                },
+            ),
+            (
+                EmptyDeck::FingerPatterns,
+                CardGenerator {
+                    finger_patterns_allowed: self
+                        .instrument
+                        .finger_patterns()
+                        .iter()
+                        .copied()
+                        .collect(),
+                    ..self.clone()
+                },
+            ),
+            (
+                EmptyDeck::Scales,
+                CardGenerator {
+                    major_scales_allowed: true,
+                    minor_scales_allowed: true,
+                    modal_scales_allowed: true,
+                    ..self.clone()
+                },
+            ),
+            (
+                EmptyDeck::ClefRanges,
+                CardGenerator {
+                    bass_clef: widen(&self.bass_clef),
+                    treble_clef: widen(&self.treble_clef),
+                    tenor_clef: widen(&self.tenor_clef),
+                    alto_clef: widen(&self.alto_clef),
+                    treble_8vb_clef: widen(&self.treble_8vb_clef),
+                    soprano_clef: widen(&self.soprano_clef),
+                    baritone_clef: widen(&self.baritone_clef),
+                    ..self.clone()
+                },
+            ),
+        ];
+        let reason = loosened
+            .into_iter()
+            .find(|(_, settings)| settings.has_cards())
+            .map_or(EmptyDeck::Combination, |(reason, _)| reason);
+        Some(reason)
+    }
+
+    /// Whether the settings make at least one card, stopping at the first.
+    fn has_cards(&self) -> bool {
+        self.card_groups(self.seeded_rng()).next().is_some()
+    }
+
+    /// The rng `card_generator` hands the groups when dealing from the seed in
+    /// the settings, so what is counted without a deck matches what is dealt.
+    fn seeded_rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(ChaCha8Rng::seed_from_u64(self.seed.unwrap_or(0)).gen())
+    }
+
+    /// How many cards are left out because spelling them needs a triple sharp
+    /// or flat and the settings don't allow those.
+    pub fn unspellable_card_count(&self) -> usize {
+        if self.triple_accidentals_allowed {
+            return 0;
+        }
+        self.candidate_groups(self.seeded_rng())
+            .filter(|group| group.iter().any(Card::needs_triple_accidental))
+            .map(|group| group.len())
+            .sum()
+    }
+
+    /// Every card the settings allow, unshuffled, one group at a time. A drill
+    /// is one group, so its parts can stay together and in order; every other
+    /// card is a group of its own. Nothing is generated until it is asked for.
+    fn card_groups<R: Rng + 'static>(&self, rng: R) -> impl Iterator<Item = Vec<Card>> + '_ {
+        self.candidate_groups(rng).filter(|group| {
+            self.triple_accidentals_allowed || !group.iter().any(Card::needs_triple_accidental)
+        })
+    }
+
+    /// The groups of `card_groups`, before cards needing triple sharps or
+    /// flats are left out.
+    fn candidate_groups<R: Rng + 'static>(&self, rng: R) -> impl Iterator<Item = Vec<Card>> + '_ {
+        let allowed_patterns: Vec<FingerPattern> = self
+            .instrument
+            .finger_patterns()
+            .iter()
+            .filter(|pattern| self.finger_patterns_allowed.contains(pattern))
+            .copied()
+            .collect();
+        let mut finger_patterns: Vec<FingerPattern> = Vec::new();
+        if self.position_cards_allowed {
+            finger_patterns.extend(&allowed_patterns);
+        }
+        if self.full_position_cards_allowed {
+            finger_patterns.extend(self.instrument.full_position_patterns());
+        }
+
+        let mut clefs = Vec::with_capacity(7);
+        if let Some(range) = &self.bass_clef {
+            clefs.push((Clef::Bass, range.clone()));
+        }
+        if let Some(range) = &self.treble_clef {
+            clefs.push((Clef::Treble, range.clone()));
+        }
+        if let Some(range) = &self.tenor_clef {
+            clefs.push((Clef::Tenor, range.clone()));
+        }
+        if let Some(range) = &self.alto_clef {
+            clefs.push((Clef::Alto, range.clone()));
+        }
+        if let Some(range) = &self.treble_8vb_clef {
+            clefs.push((Clef::TrebleOctaveDown, range.clone()));
+        }
+        if let Some(range) = &self.soprano_clef {
+            clefs.push((Clef::Soprano, range.clone()));
+        }
+        if let Some(range) = &self.baritone_clef {
+            clefs.push((Clef::Baritone, range.clone()));
+        }
+
+        let CardGenerator {
+            half_position_allowed,
+            position_1_allowed,
+            position_2_allowed,
+            position_3_allowed,
+            position_4_allowed,
+            position_5_allowed,
+            position_6_allowed,
+            position_7_allowed,
+            thumb_position_allowed,
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+            max_double_accidentals: _,
+            triple_accidentals_allowed: _,
+            max_sharps: _,
+            max_flats: _,
+            key_signatures: _,
+            instrument,
+            position_cards_allowed: _,
+            single_note_cards_allowed: _,
+            interval_cards_allowed: _,
+            full_position_cards_allowed: _,
+            scale_fragment_cards_allowed: _,
+            scale_cards_allowed: _,
+            arpeggio_cards_allowed: _,
+            double_stop_cards_allowed,
+            natural_harmonic_cards_allowed,
+            artificial_harmonic_cards_allowed,
+            sounding_pitch_shown: _,
+            major_scales_allowed: _,
+            minor_scales_allowed: _,
+            modal_scales_allowed: _,
+            scale_octaves: _,
+            finger_patterns_allowed: _,
+            bass_clef: _,
+            tenor_clef: _,
+            treble_clef: _,
+            alto_clef: _,
+            treble_8vb_clef: _,
+            soprano_clef: _,
+            baritone_clef: _,
+            shuffled_order: _,
+            string_count: _,
+            max_card_count: _,
+            quiz_mode: _,
+            seed: _,
+        } = self.clone();
+        let position_allowed = move |position| match position {
+            Position::Half => half_position_allowed,
+            Position::First => position_1_allowed,
+            Position::Second => position_2_allowed,
+            Position::Third => position_3_allowed,
+            Position::Fourth => position_4_allowed,
+            Position::Fifth => position_5_allowed,
+            Position::Sixth => position_6_allowed,
+            Position::Seventh => position_7_allowed,
+            Position::Thumb => thumb_position_allowed,
+        };
+        let strings_allowed = [
+            string_1_allowed,
+            string_2_allowed,
+            string_3_allowed,
+            string_4_allowed,
+        ];
+        let strings: Vec<_> = instrument
+            .strings()
+            .filter(|string| strings_allowed[string.number() as usize - 1])
+            .collect();
+
+        let double_stops = double_stop_cards_allowed.then(|| {
+            self.double_stop_cards(
+                clefs.clone(),
+                strings.clone(),
+                allowed_patterns,
+                position_allowed,
+            )
+        });
+        let harmonics = natural_harmonic_cards_allowed
+            .then(|| self.harmonic_cards(clefs.clone(), strings.clone()));
+        let artificial_harmonics = artificial_harmonic_cards_allowed.then(|| {
+            self.artificial_harmonic_cards(clefs.clone(), strings.clone(), position_allowed)
+        });
+        self.position_cards(
+            rng,
+            finger_patterns,
+            clefs.clone(),
+            strings.clone(),
+            position_allowed,
+        )
+        .chain(self.reading_cards(clefs.clone()))
+        .chain(double_stops.into_iter().flatten())
+        .chain(harmonics.into_iter().flatten())
+        .chain(artificial_harmonics.into_iter().flatten())
+        .map(|card| vec![card])
+        .chain(self.drill_cards(clefs, strings))
+    }
+
+    /// Position and full-position cards: each finger pattern from every note,
+    /// on the first allowed string that puts the hand in an allowed position,
+    /// in every clef whose range holds the notes.
+    fn position_cards<R: Rng + 'static>(
+        &self,
+        mut rng: R,
+        finger_patterns: Vec<FingerPattern>,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+        strings: Vec<InstrumentString>,
+        position_allowed: impl Fn(Position) -> bool + 'static,
+    ) -> impl Iterator<Item = Card> + '_ {
+        let offsets = self.instrument.string_offsets();
+        (0..=127u8)
+            .filter_map(Note::from_midi)
+            .flat_map(move |start| {
+                let mut cards = Vec::new();
+                for start_note in start.all_enharmonic_equivalents() {
+                    // a backward extension is named after where the closed hand would be,
+                    // so each pattern can put the same note in a different position
+                    let fingers = finger_patterns.iter().filter_map(|&pattern| {
+                        let hand = pattern.extension().hand_note(start_note)?;
+                        strings.iter().find_map(|&string| {
+                            string
+                                .position_of(hand)
+                                .filter(|&position| position_allowed(position))
+                                .map(|position| (string, position, pattern))
+                        })
+                    });
+                    // the same note can also be the thumb, higher up a lower string
+                    let thumb = strings
+                        .iter()
+                        .filter(|_| {
+                            self.position_cards_allowed && position_allowed(Position::Thumb)
+                        })
+                        .find(|string| string.thumb_reaches(start_note))
+                        .into_iter()
+                        .flat_map(|&string| {
+                            self.instrument
+                                .thumb_patterns()
+                                .iter()
+                                .map(move |&pattern| (string, Position::Thumb, pattern))
+                        });
+                    for (string, position, pattern) in fingers.chain(thumb) {
+                        let Some(notes) = pattern.notes(start_note) else {
+                            continue;
+                        };
+                        if !self.accidentals_allowed(&notes) {
+                            continue;
+                        }
+                        let fingering = Fingering {
+                            string,
+                            position,
+                            pattern: Some(pattern),
+                            notes: pattern
+                                .fingers()
+                                .iter()
+                                .map(|&finger| (finger, string))
+                                .collect(),
+                            shifts: Vec::new(),
+                        };
+                        for (clef, range) in &clefs {
+                            let mut fingering = fingering.clone();
+                            let notes = match (self.shuffled_order, self.string_count) {
+                                (false, 0) => notes.clone(),
+                                (shuffled, _) => {
+                                    // play some of the notes on higher strings, keeping the hand in place
+                                    let reach = (self.string_count as usize)
+                                        .clamp(1, fingering.string.strings_above() + 1);
+                                    let mut notes: Vec<_> = notes
+                                        .iter()
+                                        .zip(&fingering.notes)
+                                        .map(|(&note, &(finger, string))| {
+                                            let across = rng.gen_range(0..reach);
+                                            match (note + offsets[across], string.up(across)) {
+                                                (Some(note), Some(string)) => {
+                                                    (note, (finger, string))
+                                                }
+                                                _ => (note, (finger, string)),
+                                            }
+                                        })
+                                        .collect();
+                                    if shuffled {
+                                        notes.shuffle(&mut rng);
+                                    } else {
+                                        notes.sort_by_key(|(n, _)| n.midi());
+                                    }
+                                    fingering.notes =
+                                        notes.iter().map(|&(_, played)| played).collect();
+                                    notes.into_iter().map(|(note, _)| note).collect()
+                                }
+                            };
+                            if notes.iter().all(|note| range.contains(&note.midi())) {
+                                let mut card = Card {
+                                    clef: *clef,
+                                    kind: if notes.len() == 4 {
+                                        CardKind::FullPosition
+                                    } else {
+                                        CardKind::Position
+                                    },
+                                    notes,
+                                    key: KeySignature::default(),
+                                    fingering: Some(fingering),
+                                    drill: None,
+                                    harmonic: None,
+                                    sounding_shown: false,
+                                };
+                                if self.key_signatures {
+                                    card.key = card.best_key(self.max_sharps, self.max_flats);
+                                }
+                                cards.push(card);
+                            }
+                        }
+                    }
+                }
+                cards
+            })
+    }
+
+    /// Scale and arpeggio drills starting on every note in each clef's range,
+    /// each split into cards of at most eight notes. Accidental limits apply
+    /// to the scale's spelling rather than to every note played, and drills
+    /// with no fingering on the allowed strings are left out.
+    fn drill_cards(
+        &self,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+        strings: Vec<InstrumentString>,
+    ) -> impl Iterator<Item = Vec<Card>> + '_ {
+        let scales: Vec<Scale> = Scale::ALL
+            .into_iter()
+            .filter(|scale| {
+                if scale.is_minor() {
+                    self.minor_scales_allowed
+                } else if scale.is_mode() {
+                    self.modal_scales_allowed
+                } else {
+                    self.major_scales_allowed
+                }
+            })
+            .collect();
+        let mut arpeggios = Vec::with_capacity(2);
+        if self.scale_cards_allowed {
+            arpeggios.push(false);
+        }
+        if self.arpeggio_cards_allowed {
+            arpeggios.push(true);
+        }
+        clefs.into_iter().flat_map(move |(clef, range)| {
+            let (scales, arpeggios, strings) = (scales.clone(), arpeggios.clone(), strings.clone());
+            let tonics = range
+                .clone()
+                .filter_map(Note::from_midi)
+                .flat_map(Note::all_enharmonic_equivalents);
+            tonics.flat_map(move |tonic| {
+                let mut drills = Vec::new();
+                for &scale in &scales {
+                    for &arpeggio in &arpeggios {
+                        let drill = Drill {
+                            tonic,
+                            scale,
+                            arpeggio,
+                            octaves: self.scale_octaves.clamp(1, 4),
+                        };
+                        let Some(notes) = drill.notes() else {
+                            continue;
+                        };
+                        let mut spelling: Vec<Note> = Vec::with_capacity(7);
+                        for note in &notes {
+                            if !spelling
+                                .iter()
+                                .any(|n| (n.letter, n.accidental) == (note.letter, note.accidental))
+                            {
+                                spelling.push(*note);
+                            }
+                        }
+                        if !notes.iter().all(|note| range.contains(&note.midi()))
+                            || !self.accidentals_allowed(&spelling)
+                        {
+                            continue;
+                        }
+                        let Some(stops) = scales::fingering(&notes, &strings) else {
+                            continue;
+                        };
+                        let key = if self.key_signatures {
+                            Card {
+                                clef,
+                                kind: CardKind::Drill,
+                                notes: spelling,
+                                key: KeySignature::default(),
+                                fingering: None,
+                                drill: None,
+                                harmonic: None,
+                                sounding_shown: false,
+                            }
+                            .best_key(self.max_sharps, self.max_flats)
+                        } else {
+                            KeySignature::default()
+                        };
+
+                        let parts = notes.len().div_ceil(8);
+                        let mut start = 0;
+                        let mut cards = Vec::with_capacity(parts);
+                        for part in 0..parts {
+                            // spread the notes evenly, so no part is much shorter than the rest
+                            let end = start
+                                + notes.len() / parts
+                                + usize::from(part < notes.len() % parts);
+                            let shifts = (start + 1..end)
+                                .filter(|&i| stops[i].position != stops[i - 1].position)
+                                .map(|i| (i - start, stops[i].position))
+                                .collect();
+                            cards.push(Card {
+                                clef,
+                                kind: CardKind::Drill,
+                                notes: notes[start..end].to_vec(),
+                                key,
+                                fingering: Some(Fingering {
+                                    string: stops[start].string,
+                                    position: stops[start].position,
+                                    pattern: None,
+                                    notes: stops[start..end]
+                                        .iter()
+                                        .map(|stop| (stop.finger, stop.string))
+                                        .collect(),
+                                    shifts,
+                                }),
+                                drill: Some(DrillPart {
+                                    drill,
+                                    part: part + 1,
+                                    parts,
+                                }),
+                                harmonic: None,
+                                sounding_shown: false,
+                            });
+                            start = end;
+                        }
+                        drills.push(cards);
+                    }
+                }
+                drills
+            })
+        })
+    }
+
+    /// Double stops on each pair of neighbouring allowed strings, taking one
+    /// note of a finger pattern on the lower string and one of the same
+    /// pattern, with the hand in the same place, on the upper string. The
+    /// position rules are the same as for position cards.
+    fn double_stop_cards(
+        &self,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+        strings: Vec<InstrumentString>,
+        patterns: Vec<FingerPattern>,
+        position_allowed: impl Fn(Position) -> bool + 'static,
+    ) -> impl Iterator<Item = Card> + '_ {
+        let across = self.instrument.string_offsets()[1];
+        // patterns sharing a finger give the same double stop more than once,
+        // always from the same lower note, so repeats are caught note by note
+        (0..=127u8).flat_map(move |low_midi| {
+            let mut seen = HashSet::new();
+            let mut cards = Vec::new();
+            // no hand spans more than a fifth
+            let starts = (low_midi.saturating_sub(7)..=low_midi)
+                .filter_map(Note::from_midi)
+                .flat_map(Note::all_enharmonic_equivalents);
+            for start in starts {
+                for &pattern in &patterns {
+                    let Some(hand) = pattern.extension().hand_note(start) else {
+                        continue;
+                    };
+                    for &string in &strings {
+                        let Some(upper) = string.up(1).filter(|upper| strings.contains(upper))
+                        else {
+                            continue;
+                        };
+                        let Some(position) = string
+                            .position_of(hand)
+                            .filter(|&position| position_allowed(position))
+                        else {
+                            continue;
+                        };
+                        let (Some(low_notes), Some(high_notes)) = (
+                            pattern.notes(start),
+                            (start + across).and_then(|upper_start| pattern.notes(upper_start)),
+                        ) else {
+                            continue;
+                        };
+                        for (&low, &low_finger) in low_notes.iter().zip(pattern.fingers()) {
+                            if low.midi() != low_midi {
+                                continue;
+                            }
+                            for (&high, &high_finger) in high_notes.iter().zip(pattern.fingers()) {
+                                let notes = vec![low, high];
+                                if !self.accidentals_allowed(&notes) {
+                                    continue;
+                                }
+                                for (clef, range) in &clefs {
+                                    if !notes.iter().all(|note| range.contains(&note.midi())) {
+                                        continue;
+                                    }
+                                    let mut card = Card {
+                                        clef: *clef,
+                                        kind: CardKind::DoubleStop,
+                                        notes: notes.clone(),
+                                        key: KeySignature::default(),
+                                        fingering: Some(Fingering {
+                                            string,
+                                            position,
+                                            pattern: None,
+                                            notes: vec![(low_finger, string), (high_finger, upper)],
+                                            shifts: Vec::new(),
+                                        }),
+                                        drill: None,
+                                        harmonic: None,
+                                        sounding_shown: false,
+                                    };
+                                    if !seen.insert((card.id(), low_finger, high_finger)) {
+                                        continue;
+                                    }
+                                    if self.key_signatures {
+                                        card.key = card.best_key(self.max_sharps, self.max_flats);
+                                    }
+                                    cards.push(card);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            cards
+        })
+    }
+
+    /// A natural harmonic at each node of every allowed string, written where
+    /// the string is touched.
+    fn harmonic_cards(
+        &self,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+        strings: Vec<InstrumentString>,
+    ) -> impl Iterator<Item = Card> + '_ {
+        clefs.into_iter().flat_map(move |(clef, range)| {
+            let harmonics = strings
+                .clone()
+                .into_iter()
+                .flat_map(|string| HarmonicNode::ALL.map(|node| NaturalHarmonic { string, node }));
+            harmonics.filter_map(move |harmonic| {
+                let note = harmonic.touched()?;
+                if !range.contains(&note.midi()) || !self.accidentals_allowed(&[note]) {
+                    return None;
+                }
+                let mut card = Card {
+                    clef,
+                    kind: CardKind::NaturalHarmonic,
+                    notes: vec![note],
+                    key: KeySignature::default(),
+                    fingering: None,
+                    drill: None,
+                    harmonic: Some(harmonic),
+                    sounding_shown: self.sounding_pitch_shown,
+                };
+                if self.key_signatures {
+                    card.key = card.best_key(self.max_sharps, self.max_flats);
+                }
+                Some(card)
+            })
+        })
+    }
+
+    /// An artificial harmonic on every stopped note in each clef's range, on
+    /// the first allowed string that reaches it. Cellists and bassists stop
+    /// the note with the thumb and touch with the third finger; violinists
+    /// and violists use the first and fourth.
+    fn artificial_harmonic_cards(
+        &self,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+        strings: Vec<InstrumentString>,
+        position_allowed: impl Fn(Position) -> bool + Copy + 'static,
+    ) -> impl Iterator<Item = Card> + '_ {
+        let thumb = !self.instrument.thumb_patterns().is_empty();
+        clefs.into_iter().flat_map(move |(clef, range)| {
+            let strings = strings.clone();
+            let stopped_notes = range
+                .clone()
+                .filter_map(Note::from_midi)
+                .flat_map(Note::all_enharmonic_equivalents);
+            stopped_notes.filter_map(move |stopped| {
+                let touched = (stopped + Interval::new(IntervalQuality::Perfect, 4).ok()?)?;
+                let notes = vec![stopped, touched];
+                if !range.contains(&touched.midi()) || !self.accidentals_allowed(&notes) {
+                    return None;
+                }
+                let (string, position) = strings.iter().find_map(|&string| {
+                    let position = if thumb {
+                        string.thumb_reaches(stopped).then_some(Position::Thumb)
+                    } else {
+                        string.position_of(stopped)
+                    };
+                    position
+                        .filter(|&position| position_allowed(position))
+                        .map(|position| (string, position))
+                })?;
+                let fingers = if thumb { (0, 3) } else { (1, 4) };
+                let mut card = Card {
+                    clef,
+                    kind: CardKind::ArtificialHarmonic,
+                    notes,
+                    key: KeySignature::default(),
+                    fingering: Some(Fingering {
+                        string,
+                        position,
+                        pattern: None,
+                        notes: vec![(fingers.0, string), (fingers.1, string)],
+                        shifts: Vec::new(),
+                    }),
+                    drill: None,
+                    harmonic: None,
+                    sounding_shown: self.sounding_pitch_shown,
+                };
+                if self.key_signatures {
+                    card.key = card.best_key(self.max_sharps, self.max_flats);
+                }
+                Some(card)
+            })
+        })
+    }
+
+    /// Single-note, interval and scale-fragment cards, which are about reading
+    /// the staff and so have no fingering.
+    fn reading_cards(
+        &self,
+        clefs: Vec<(Clef, RangeInclusive<u8>)>,
+    ) -> impl Iterator<Item = Card> + '_ {
+        use IntervalQuality as Q;
+        clefs.into_iter().flat_map(move |(clef, range)| {
+            let spellings = range
+                .clone()
+                .filter_map(Note::from_midi)
+                .flat_map(Note::all_enharmonic_equivalents);
+            spellings.flat_map(move |note| {
+                let mut candidates = Vec::new();
+                if self.single_note_cards_allowed {
+                    candidates.push((CardKind::SingleNote, vec![note]));
+                }
+                if self.interval_cards_allowed {
+                    for (quality, interval) in [
+                        (Q::Minor, 2),
+                        (Q::Major, 2),
+                        (Q::Minor, 3),
+                        (Q::Major, 3),
+                        (Q::Perfect, 4),
+                        (Q::Augmented, 4),
+                        (Q::Diminished, 5),
+                        (Q::Perfect, 5),
+                        (Q::Minor, 6),
+                        (Q::Major, 6),
+                        (Q::Minor, 7),
+                        (Q::Major, 7),
+                        (Q::Perfect, 8),
+                    ] {
+                        if let Some(top) = Interval::new(quality, interval)
+                            .ok()
+                            .and_then(|interval| note + interval)
+                        {
+                            candidates.push((CardKind::Interval, vec![note, top]));
+                        }
+                    }
+                }
+                if self.scale_fragment_cards_allowed {
+                    let scale: Option<Vec<Note>> = [
+                        (Q::Perfect, 1),
+                        (Q::Major, 2),
+                        (Q::Major, 3),
+                        (Q::Perfect, 4),
+                        (Q::Perfect, 5),
+                    ]
+                    .into_iter()
+                    .map(|(quality, interval)| note + Interval::new(quality, interval).ok()?)
+                    .collect();
+                    if let Some(scale) = scale {
+                        candidates.push((
+                            CardKind::ScaleFragment,
+                            scale.iter().rev().copied().collect(),
+                        ));
+                        candidates.push((CardKind::ScaleFragment, scale));
+                    }
+                }
+                let range = range.clone();
+                candidates.into_iter().filter_map(move |(kind, notes)| {
+                    if !notes.iter().all(|note| range.contains(&note.midi()))
+                        || !self.accidentals_allowed(&notes)
+                    {
+                        return None;
+                    }
+                    let mut card = Card {
+                        clef,
+                        kind,
+                        notes,
+                        key: KeySignature::default(),
+                        fingering: None,
+                        drill: None,
+                        harmonic: None,
+                        sounding_shown: false,
+                    };
+                    if self.key_signatures {
+                        card.key = card.best_key(self.max_sharps, self.max_flats);
+                    }
+                    Some(card)
+                })
+            })
+        })
+    }
+
+    /// Whether `notes` stay within the allowed number of sharps, flats and
+    /// double accidentals.
+    fn accidentals_allowed(&self, notes: &[Note]) -> bool {
+        let mut sharps = 0;
+        let mut flats = 0;
+        let mut double = 0;
+        for note in notes {
+            match note.accidental {
+                Some(Accidental::Flat) => {
+                    flats += 1;
+                }
+                Some(Accidental::DoubleFlat | Accidental::TripleFlat) => {
+                    double += 1;
+                    flats += 1;
+                }
+                Some(Accidental::DoubleSharp | Accidental::TripleSharp) => {
+                    double += 1;
+                    sharps += 1;
+                }
+                Some(Accidental::Sharp) => {
+                    sharps += 1;
+                }
+                None | Some(Accidental::Natural) => {}
+            }
+        }
+        sharps <= self.max_sharps
+            && flats <= self.max_flats
+            && double <= self.max_double_accidentals
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn sampled_decks() {
+    let mut settings = CardGenerator::tenor_clef_advanced();
+    settings.max_card_count = None;
+    let every_card = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert_eq!(
+        settings.card_count(&mut rand::thread_rng()),
+        every_card.len()
+    );
+
+    // a card the student got wrong last session always makes the cut
+    let mut schedule = Schedule::default();
+    let missed = &every_card[every_card.len() / 2];
+    schedule.grade(missed, false);
+    schedule.start_session();
+    settings.max_card_count = NonZeroUsize::new(5);
+    for _ in 0..10 {
+        let cards = settings.card_generator(&mut rand::thread_rng(), &schedule);
+        assert_eq!(cards.len(), 5);
+        assert_eq!(cards[0].id(), missed.id());
+    }
+
+    // a drill is ranked by its most pressing part, and dealt whole or not at all
+    let mut settings = CardGenerator::scales();
+    settings.max_card_count = None;
+    let every_card = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    let missed = every_card
+        .iter()
+        .find(|card| card.drill.is_some_and(|part| part.part == 2))
+        .unwrap();
+    let mut schedule = Schedule::default();
+    schedule.grade(missed, false);
+    schedule.start_session();
+    settings.max_card_count = NonZeroUsize::new(20);
+    for _ in 0..10 {
+        let cards = settings.card_generator(&mut rand::thread_rng(), &schedule);
+        assert!(!cards.is_empty() && cards.len() <= 20);
+        assert_eq!(cards[1].id(), missed.id());
+        let parts: Vec<DrillPart> = cards.iter().map(|card| card.drill.unwrap()).collect();
+        assert_eq!(parts[0].part, 1);
+        assert_eq!(parts[parts.len() - 1].part, parts[parts.len() - 1].parts);
+        for pair in parts.windows(2) {
+            let next = if pair[0].part < pair[0].parts {
+                pair[0].part + 1
+            } else {
+                1
+            };
+            assert_eq!(pair[1].part, next);
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn empty_deck_reasons() {
+    let settings = CardGenerator::no_sharps_flats();
+    assert_eq!(settings.empty_deck_reason(), None);
+    let empty = |change: fn(&mut CardGenerator)| {
+        let mut settings = CardGenerator::no_sharps_flats();
+        change(&mut settings);
+        settings.empty_deck_reason()
+    };
+    assert_eq!(
+        empty(|s| s.position_cards_allowed = false),
+        Some(EmptyDeck::NoCardTypes)
+    );
+    assert_eq!(empty(|s| s.bass_clef = None), Some(EmptyDeck::NoClefs));
+    assert_eq!(
+        empty(|s| s.bass_clef = Some(RangeInclusive::new(60, 50))),
+        Some(EmptyDeck::EmptyClefRanges)
+    );
+    assert_eq!(
+        empty(|s| [
+            s.string_1_allowed,
+            s.string_2_allowed,
+            s.string_3_allowed,
+            s.string_4_allowed
+        ] = [false; 4]),
+        Some(EmptyDeck::NoStrings)
+    );
+    assert_eq!(
+        empty(|s| s.bass_clef = Some(60..=61)),
+        Some(EmptyDeck::ClefRanges)
+    );
+    assert_eq!(
+        empty(|s| {
+            s.position_cards_allowed = false;
+            s.single_note_cards_allowed = true;
+            s.bass_clef = Some(61..=61);
+        }),
+        Some(EmptyDeck::Accidentals)
+    );
+    assert_eq!(
+        empty(|s| {
+            s.half_position_allowed = false;
+            s.position_1_allowed = false;
+            s.position_2_allowed = false;
+            s.position_3_allowed = false;
+            s.position_4_allowed = false;
+            s.position_5_allowed = false;
+            s.position_6_allowed = false;
+            s.position_7_allowed = false;
+            s.thumb_position_allowed = false;
+        }),
+        Some(EmptyDeck::Positions)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn triple_accidentals() {
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.scale_fragment_cards_allowed = true;
+    [
+        settings.max_sharps,
+        settings.max_flats,
+        settings.max_double_accidentals,
+    ] = [7; 3];
+    settings.max_card_count = None;
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let dropped = settings.unspellable_card_count();
+    let cards = settings.card_generator(&mut rng, &Schedule::default());
+    assert!(dropped > 0);
+    assert!(!cards.iter().any(Card::needs_triple_accidental));
+
+    settings.triple_accidentals_allowed = true;
+    let cards_with_triples = settings.card_generator(&mut rng, &Schedule::default());
+    assert_eq!(settings.unspellable_card_count(), 0);
+    assert_eq!(cards_with_triples.len(), cards.len() + dropped);
+    assert!(cards_with_triples
+        .iter()
+        .any(|card| card.notes.iter().any(|n| n.to_string() == "D###3")));
+
+    let card: Card = "D3:B##2:C###3".parse().unwrap();
+    assert_eq!(
+        card.displayed_accidentals(),
+        [Some(Accidental::DoubleSharp), Some(Accidental::TripleSharp)]
+    );
+    card.to_svg();
+}
+
+#[cfg(test)]
+#[test]
+fn settings_errors() {
+    let form = |values: &[(&str, &str)]| {
+        let values: std::collections::HashMap<String, String> = [
+            ("scale_octaves", "1"),
+            ("max_double_accidentals", "0"),
+            ("max_sharps", "2"),
+            ("max_flats", "0"),
+            ("string_count", "4"),
+            ("max_card_count", ""),
+            ("seed", ""),
+            ("bass_clef_min", "C2"),
+            ("bass_clef_max", "48"),
+        ]
+        .iter()
+        .chain(values)
+        .map(|&(id, value)| (id.to_string(), value.to_string()))
+        .collect();
+        CardGenerator::from_form(
+            "cello",
+            |id| values.get(id).cloned().unwrap_or_default(),
+            |_| true,
+        )
+    };
+    let settings = form(&[]).unwrap();
+    assert_eq!(settings.bass_clef, Some(36..=48));
+    assert_eq!(settings.tenor_clef, None);
+    assert_eq!(settings.max_sharps, 2);
+    assert_eq!(settings.max_card_count, None);
+
+    let errors = form(&[
+        ("max_sharps", "two"),
+        ("string_count", "5"),
+        ("max_card_count", "0"),
+        ("seed", "-1"),
+        ("bass_clef_min", "C4"),
+        ("tenor_clef_max", "H3"),
+        ("alto_clef_min", "C3"),
+    ])
+    .unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            SettingsError::ReversedRange {
+                field: "bass_clef_min"
+            },
+            SettingsError::Note {
+                field: "tenor_clef_max"
+            },
+            SettingsError::MissingNote {
+                field: "tenor_clef_min"
+            },
+            SettingsError::MissingNote {
+                field: "alto_clef_max"
+            },
+            SettingsError::Count {
+                field: "max_sharps",
+                min: 0,
+                max: 7
+            },
+            SettingsError::Count {
+                field: "string_count",
+                min: 1,
+                max: 4
+            },
+            SettingsError::CardLimit,
+            SettingsError::Seed,
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn settings_query() {
+    for settings in [
+        CardGenerator::no_sharps_flats(),
+        CardGenerator::three_flats(),
+        CardGenerator::tenor_clef_advanced(),
+        CardGenerator::impossible(),
+        CardGenerator::scales(),
+    ] {
+        let query = settings.to_query();
+        assert_eq!(CardGenerator::from_query(&query), Ok(settings.clone()));
+        assert_eq!(
+            CardGenerator::from_query(&format!("?{}&extra=1", query)),
+            Ok(settings)
+        );
+    }
+    let query = CardGenerator::one_sharp().to_query();
+    assert!(query.starts_with("v=11&instrument=cello&kinds=1000000000&sounding=0&scales=110&octaves=1&fingers=1,2,3-4-forward-extension&bass=36-72&"));
+    assert_eq!(
+        CardGenerator::from_query(&query.replace("v=11", "v=10")),
+        Err(())
+    );
+    // patterns are named, so a link can't switch on one the instrument doesn't have
+    assert_eq!(
+        CardGenerator::from_query(&query.replace("fingers=1,2,", "fingers=1,low-2,")),
+        Err(())
+    );
+    let fewer = CardGenerator::from_query(&query.replace("fingers=1,2,", "fingers=2,")).unwrap();
+    assert_eq!(
+        fewer.finger_patterns_allowed,
+        Instrument::Cello.patterns_named(&["2", "3 & 4, forward extension"])
+    );
+    assert_eq!(
+        CardGenerator::from_query(&query.replace("=cello", "=lute")),
+        Err(())
+    );
+    assert_eq!(
+        CardGenerator::from_query(&query.replace("strings=1111", "strings=111")),
+        Err(())
+    );
+    assert_eq!(CardGenerator::from_query(""), Err(()));
+    // a hand-edited link gets the same checks as the menu
+    for (from, to) in [
+        ("bass=36-72", "bass=72-36"),
+        ("bass=36-72", "bass=36-200"),
+        ("octaves=1", "octaves=9"),
+        ("span=1", "span=0"),
+    ] {
+        assert!(query.contains(from));
+        assert_eq!(CardGenerator::from_query(&query.replace(from, to)), Err(()));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn preset_forms() {
+    for settings in [
+        CardGenerator::no_sharps_flats(),
+        CardGenerator::one_flat(),
+        CardGenerator::one_sharp(),
+        CardGenerator::two_flats(),
+        CardGenerator::two_sharps(),
+        CardGenerator::three_flats(),
+        CardGenerator::three_sharps(),
+        CardGenerator::tenor_clef_initial(),
+        CardGenerator::tenor_clef_advanced(),
+        CardGenerator::treble_clef_initial(),
+        CardGenerator::treble_clef_advanced(),
+        CardGenerator::advanced(),
+        CardGenerator::scales(),
+        CardGenerator::thumb_position(),
+        CardGenerator::thumb_position_advanced(),
+        CardGenerator::impossible(),
+        CardGenerator::violin(),
+        CardGenerator::viola(),
+        CardGenerator::double_bass(),
+    ] {
+        assert_eq!(settings.through_form(), Ok(settings.clone()));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn cello_card_generator() {
+    let mut rng = rand::thread_rng();
+    CardGenerator::no_sharps_flats()
+        .card_generator(&mut rng, &Schedule::default())
+        .into_iter()
+        .for_each(|c| {
+            println!("{c:?}");
+        });
+}
+
+#[cfg(test)]
+#[test]
+fn instrument_presets() {
+    for (settings, instrument) in [
+        (CardGenerator::no_sharps_flats(), Instrument::Cello),
+        (CardGenerator::violin(), Instrument::Violin),
+        (CardGenerator::viola(), Instrument::Viola),
+        (CardGenerator::double_bass(), Instrument::Bass),
+    ] {
+        let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+        assert!(!cards.is_empty(), "no {:?} cards", instrument);
+        for card in cards {
+            assert_eq!(card.instrument(), instrument);
+            let fingering = card.fingering().unwrap();
+            assert!(card.accepts(fingering.string, fingering.position));
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn thumb_position() {
+    let string = Instrument::Cello.string_named("A").unwrap();
+    assert!(!string.thumb_reaches("G#4".parse().unwrap()));
+    assert!(string.thumb_reaches("A4".parse().unwrap()));
+    assert!(string.thumb_reaches("A5".parse().unwrap()));
+    assert!(!string.thumb_reaches("B5".parse().unwrap()));
+    assert!(!Instrument::Violin
+        .string_named("E")
+        .unwrap()
+        .thumb_reaches("E6".parse().unwrap()));
+    // A4 is both the octave harmonic under the thumb and 7th position
+    let card: Card = "G4:A4:B4:C5".parse().unwrap();
+    assert!(card.accepts(string, Position::Thumb));
+    assert!(card.accepts(string, Position::Seventh));
+
+    let cards = CardGenerator::thumb_position()
+        .card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    for card in cards {
+        let fingering = card.fingering().unwrap();
+        assert_eq!(fingering.position, Position::Thumb);
+        assert_eq!(fingering.pattern.unwrap().fingers()[0], 0);
+        assert!(card.accepts(fingering.string, Position::Thumb));
+        assert!(card.answer_svg().to_string().contains("<circle"));
+    }
+    assert!(!CardGenerator::thumb_position_advanced()
+        .card_generator(&mut rand::thread_rng(), &Schedule::default())
+        .is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn seeded_decks() {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    let mut settings = CardGenerator::treble_clef_advanced();
+    settings.shuffled_order = true;
+    settings.string_count = 2;
+    let deck =
+        |seed| settings.card_generator(&mut ChaCha8Rng::seed_from_u64(seed), &Schedule::default());
+    let first = deck(42);
+    assert!(first.len() > 1);
+    assert_eq!(first, deck(42));
+    assert_ne!(first, deck(43));
+    settings.max_card_count = None;
+    settings.seed = Some(42);
+    let dealt = settings.card_generator(&mut ChaCha8Rng::seed_from_u64(42), &Schedule::default());
+    assert_eq!(
+        settings.card_count(&mut ChaCha8Rng::seed_from_u64(42)),
+        dealt.len()
+    );
+    assert!(settings.has_cards());
+
+    let mut settings = CardGenerator::no_sharps_flats();
+    let seed = settings.pin_seed();
+    assert_eq!(settings.pin_seed(), seed);
+    assert!(settings.to_query().ends_with(&format!("&seed={}", seed)));
+}
+
+/// A bowed string instrument, described by the data the generator needs: the
+/// open strings, how they are tuned against each other and the finger patterns
+/// a hand takes in one position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument {
+    Cello,
+    Violin,
+    Viola,
+    Bass,
+}
+
+impl Instrument {
+    pub const ALL: [Instrument; 4] = [
+        Instrument::Cello,
+        Instrument::Violin,
+        Instrument::Viola,
+        Instrument::Bass,
+    ];
+
+    pub fn id(self) -> &'static str {
+        use Instrument as I;
+        match self {
+            I::Cello => "cello",
+            I::Violin => "violin",
+            I::Viola => "viola",
+            I::Bass => "bass",
+        }
+    }
+
+    /// Open strings from string I, the highest, down. Double bass parts are
+    /// written an octave above where they sound, and so are its strings here.
+    const fn open_strings(self) -> [Note; 4] {
+        use Instrument as I;
+        use Letter as L;
+        const fn natural(letter: Letter, octave: i8) -> Note {
+            Note {
+                letter,
+                octave,
+                accidental: None,
+            }
+        }
+        match self {
+            I::Cello => [
+                natural(L::A, 3),
+                natural(L::D, 3),
+                natural(L::G, 2),
+                natural(L::C, 2),
+            ],
+            I::Violin => [
+                natural(L::E, 5),
+                natural(L::A, 4),
+                natural(L::D, 4),
+                natural(L::G, 3),
+            ],
+            I::Viola => [
+                natural(L::A, 4),
+                natural(L::D, 4),
+                natural(L::G, 3),
+                natural(L::C, 3),
+            ],
+            I::Bass => [
+                natural(L::G, 3),
+                natural(L::D, 3),
+                natural(L::A, 2),
+                natural(L::E, 2),
+            ],
+        }
+    }
+
+    /// The same finger on the next strings up, starting with the string itself.
+    fn string_offsets(self) -> [Interval; 4] {
+        use IntervalQuality as Q;
+        match self {
+            Instrument::Bass => [
+                Interval {
+                    interval: 1,
+                    quality: Q::Perfect,
+                },
+                Interval {
+                    interval: 4,
+                    quality: Q::Perfect,
+                },
+                Interval {
+                    interval: 7,
+                    quality: Q::Minor,
+                },
+                Interval {
+                    interval: 10,
+                    quality: Q::Minor,
+                },
+            ],
+            _ => [
+                Interval {
+                    interval: 1,
+                    quality: Q::Perfect,
+                },
+                Interval {
+                    interval: 5,
+                    quality: Q::Perfect,
+                },
+                Interval {
+                    interval: 9,
+                    quality: Q::Major,
+                },
+                Interval {
+                    interval: 13,
+                    quality: Q::Major,
+                },
+            ],
+        }
+    }
+
+    /// The patterns in the order of the finger pattern switches in the settings.
+    pub fn finger_patterns(self) -> &'static [FingerPattern] {
+        use Instrument as I;
+        match self {
+            I::Cello => &CELLO_PATTERNS,
+            I::Violin | I::Viola => &VIOLIN_PATTERNS,
+            I::Bass => &BASS_PATTERNS,
+        }
+    }
+
+    /// The instrument's finger patterns with these names, for the presets.
+    fn patterns_named(self, names: &[&str]) -> HashSet<FingerPattern> {
+        let patterns: HashSet<FingerPattern> = self
+            .finger_patterns()
+            .iter()
+            .filter(|pattern| names.contains(&pattern.name().as_str()))
+            .copied()
+            .collect();
+        debug_assert_eq!(
+            patterns.len(),
+            names.len(),
+            "unknown {:?} finger pattern in {:?}",
+            self,
+            names
+        );
+        patterns
+    }
+
+    /// Patterns for thumb position. Violin and viola don't play in it.
+    pub fn thumb_patterns(self) -> &'static [FingerPattern] {
+        use Instrument as I;
+        match self {
+            I::Cello => &CELLO_THUMB_PATTERNS,
+            I::Violin | I::Viola => &[],
+            I::Bass => &BASS_THUMB_PATTERNS,
+        }
+    }
+
+    /// Patterns with all four fingers down, for full-position cards. The
+    /// double bass only uses three fingers in a position, so has none.
+    pub fn full_position_patterns(self) -> &'static [FingerPattern] {
+        use Instrument as I;
+        match self {
+            I::Cello => &CELLO_FULL_POSITION,
+            I::Violin | I::Viola => &VIOLIN_FULL_POSITION,
+            I::Bass => &[],
+        }
+    }
+
+    /// String `number`, counting from 1 for the highest string.
+    pub fn string(self, number: u8) -> Option<InstrumentString> {
+        (1..=4).contains(&number).then_some(InstrumentString {
+            instrument: self,
+            number,
+        })
+    }
+
+    /// Every string, from the highest down.
+    pub fn strings(self) -> impl Iterator<Item = InstrumentString> {
+        (1..=4).filter_map(move |number| self.string(number))
+    }
+
+    /// Looks a string up by the name of its open note, such as "A".
+    pub fn string_named(self, name: &str) -> Option<InstrumentString> {
+        self.strings().find(|string| string.name() == name)
+    }
+}
+
+impl FromStr for Instrument {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Instrument::ALL
+            .into_iter()
+            .find(|instrument| instrument.id() == s)
+            .ok_or(())
+    }
+}
+
+/// One string of an instrument. Strings are numbered from the highest, as
+/// players do, so the cello's A string is string 1 and its C string string 4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstrumentString {
+    instrument: Instrument,
+    number: u8,
+}
+
+impl InstrumentString {
+    pub(crate) const fn open_note(self) -> Note {
+        self.instrument.open_strings()[self.number as usize - 1]
+    }
+
+    pub fn instrument(self) -> Instrument {
+        self.instrument
+    }
+
+    pub fn number(self) -> u8 {
+        self.number
+    }
+
+    pub fn name(self) -> &'static str {
+        use Letter as L;
+        match self.open_note().letter {
+            L::C => "C",
+            L::D => "D",
+            L::E => "E",
+            L::F => "F",
+            L::G => "G",
+            L::A => "A",
+            L::B => "B",
+        }
+    }
+
+    fn strings_above(self) -> usize {
+        self.number as usize - 1
+    }
+
+    /// The string `count` strings higher, if there is one.
+    fn up(self, count: usize) -> Option<InstrumentString> {
+        self.strings_above()
+            .checked_sub(count)
+            .and_then(|above| self.instrument.string(above as u8 + 1))
+    }
+
+    /// Which position puts the first finger on `note` on this string. Every
+    /// instrument here counts positions the same way, by the step of the scale
+    /// the first finger is on, with half position a semitone above the open
+    /// string.
+    pub fn position_of(self, note: Note) -> Option<Position> {
+        let (number, semitones) = self.distance_to(note);
+        match (number, semitones) {
+            (1 | 2, 1) => Some(Position::Half),
+            (2..=8, _) => Position::ALL.get(number as usize - 1).copied(),
+            _ => None,
+        }
+    }
+
+    /// Whether the thumb can stop `note` in thumb position: anywhere from the
+    /// octave harmonic up to two octaves above the open string, on instruments
+    /// that play in thumb position at all.
+    pub fn thumb_reaches(self, note: Note) -> bool {
+        let (number, _) = self.distance_to(note);
+        !self.instrument.thumb_patterns().is_empty() && (8..=15).contains(&number)
+    }
+
+    /// The step of the scale `note` is on counting the open string as 1, and
+    /// how many semitones above the open string it is.
+    fn distance_to(self, note: Note) -> (i16, i16) {
+        let open = self.open_note();
+        // widened, since the generator also feeds in notes far off the fingerboard
+        let number = (note.octave as i16 - open.octave as i16) * 7
+            + (note.letter as i16 - open.letter as i16)
+            + 1;
+        (number, note.midi() as i16 - open.midi() as i16)
+    }
+
+    /// Whether a cell starting on `note` is played in `position` on this
+    /// string.
+    pub fn plays_in(self, note: Note, position: Position) -> bool {
+        match position {
+            Position::Thumb => self.thumb_reaches(note),
+            position => self.position_of(note) == Some(position),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Half,
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    /// The thumb lies across the strings on the octave harmonic or above.
+    Thumb,
+}
+
+impl Position {
+    const ALL: [Position; 9] = {
+        use Position::*;
+        [
+            Half, First, Second, Third, Fourth, Fifth, Sixth, Seventh, Thumb,
+        ]
+    };
+
+    pub fn label(self) -> &'static str {
+        use Position as P;
+        match self {
+            P::Half => "half",
+            P::First => "1st",
+            P::Second => "2nd",
+            P::Third => "3rd",
+            P::Fourth => "4th",
+            P::Fifth => "5th",
+            P::Sixth => "6th",
+            P::Seventh => "7th",
+            P::Thumb => "thumb",
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Position::ALL
+            .into_iter()
+            .find(|position| position.label() == s)
+            .ok_or(())
+    }
+}
+
+/// How the hand is shaped for a finger pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// Every finger in its place for the position.
+    Closed,
+    /// The first finger reaches back a semitone, the rest stay in the position.
+    Backward,
+    /// The first finger stays and the rest reach forward a semitone.
+    Forward,
+}
+
+impl Extension {
+    pub fn label(self) -> &'static str {
+        match self {
+            Extension::Closed => "closed",
+            Extension::Backward => "backward extension",
+            Extension::Forward => "forward extension",
+        }
+    }
+
+    /// Where the first finger would be with the hand closed, which is what
+    /// names the position. `None` if that note can't be spelled.
+    fn hand_note(self, first_finger: Note) -> Option<Note> {
+        match self {
+            Extension::Closed | Extension::Forward => Some(first_finger),
+            Extension::Backward => {
+                first_finger
+                    + Interval {
+                        quality: IntervalQuality::Augmented,
+                        interval: 1,
+                    }
+            }
+        }
+    }
+}
+
+/// Which fingers play a three-note cell in one position, how far above the
+/// first finger the other two notes are, and how the hand stretches to reach
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FingerPattern {
+    id: &'static str,
+    extension: Extension,
+    fingers: &'static [u8],
+    /// From the first finger to each of the others.
+    intervals: &'static [(IntervalQuality, i8)],
+}
+
+impl FingerPattern {
+    const fn new(
+        id: &'static str,
+        extension: Extension,
+        fingers: &'static [u8],
+        intervals: &'static [(IntervalQuality, i8)],
+    ) -> Self {
+        FingerPattern {
+            id,
+            extension,
+            fingers,
+            intervals,
+        }
+    }
+
+    /// The intervals from the first finger to each later note.
+    fn intervals(self) -> impl Iterator<Item = Interval> {
+        self.intervals
+            .iter()
+            .map(|&(quality, interval)| Interval { quality, interval })
+    }
+
+    /// The fingers playing each note of the pattern, 0 being the thumb.
+    const fn fingers(self) -> &'static [u8] {
+        self.fingers
+    }
+
+    /// The notes of the pattern with the first finger on `first`, or `None`
+    /// if one can't be spelled.
+    fn notes(self, first: Note) -> Option<Vec<Note>> {
+        std::iter::once(Some(first))
+            .chain(self.intervals().map(|interval| first + interval))
+            .collect()
+    }
+
+    pub fn id(self) -> &'static str {
+        self.id
+    }
+
+    pub fn extension(self) -> Extension {
+        self.extension
+    }
+
+    /// The id, followed by the extension for patterns that stretch the hand.
+    pub fn name(self) -> String {
+        match self.extension {
+            Extension::Closed => self.id.to_string(),
+            extension => format!("{}, {}", self.id, extension.label()),
+        }
+    }
+
+    /// The name as it goes in a link, with only letters and digits kept:
+    /// "3 & 4, backward extension" is "3-4-backward-extension".
+    pub fn key(self) -> String {
+        self.name()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+}
+
+/// The backward extension plays the same notes as "3 & 4", a position higher.
+const CELLO_PATTERNS: [FingerPattern; 5] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [
+        FingerPattern::new("1", E::Closed, &[1, 2, 4], &[(Q::Minor, 2), (Q::Minor, 3)]),
+        FingerPattern::new("2", E::Closed, &[1, 3, 4], &[(Q::Major, 2), (Q::Minor, 3)]),
+        FingerPattern::new(
+            "3 & 4",
+            E::Forward,
+            &[1, 2, 4],
+            &[(Q::Major, 2), (Q::Major, 3)],
+        ),
+        FingerPattern::new(
+            "5",
+            E::Forward,
+            &[1, 3, 4],
+            &[(Q::Augmented, 2), (Q::Major, 3)],
+        ),
+        FingerPattern::new(
+            "3 & 4",
+            E::Backward,
+            &[1, 2, 4],
+            &[(Q::Major, 2), (Q::Major, 3)],
+        ),
+    ]
+};
+
+/// Violin and viola fingers span a fourth, so all four fit in a position.
+const VIOLIN_PATTERNS: [FingerPattern; 4] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [
+        FingerPattern::new(
+            "low 2",
+            E::Closed,
+            &[1, 2, 3],
+            &[(Q::Minor, 2), (Q::Minor, 3)],
+        ),
+        FingerPattern::new(
+            "high 2",
+            E::Closed,
+            &[1, 2, 3],
+            &[(Q::Major, 2), (Q::Minor, 3)],
+        ),
+        FingerPattern::new(
+            "high 3",
+            E::Closed,
+            &[1, 2, 3],
+            &[(Q::Major, 2), (Q::Major, 3)],
+        ),
+        FingerPattern::new(
+            "3 & 4",
+            E::Closed,
+            &[1, 3, 4],
+            &[(Q::Minor, 3), (Q::Perfect, 4)],
+        ),
+    ]
+};
+
+/// Double bass fingers 1, 2 and 4 are a semitone apart in every position.
+const BASS_PATTERNS: [FingerPattern; 1] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [FingerPattern::new(
+        "1 2 4",
+        E::Closed,
+        &[1, 2, 4],
+        &[(Q::Minor, 2), (Q::Major, 2)],
+    )]
+};
+
+/// In thumb position the thumb stops the lowest note and the fingers fall a
+/// step or so apart above it.
+const CELLO_THUMB_PATTERNS: [FingerPattern; 3] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [
+        FingerPattern::new(
+            "thumb 1 2",
+            E::Closed,
+            &[0, 1, 2],
+            &[(Q::Major, 2), (Q::Minor, 3)],
+        ),
+        FingerPattern::new(
+            "thumb 1 2 open",
+            E::Closed,
+            &[0, 1, 2],
+            &[(Q::Major, 2), (Q::Major, 3)],
+        ),
+        FingerPattern::new(
+            "thumb 1 3",
+            E::Closed,
+            &[0, 1, 3],
+            &[(Q::Major, 2), (Q::Perfect, 4)],
+        ),
+    ]
+};
+
+const BASS_THUMB_PATTERNS: [FingerPattern; 2] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [
+        FingerPattern::new(
+            "thumb 1 2",
+            E::Closed,
+            &[0, 1, 2],
+            &[(Q::Minor, 2), (Q::Major, 2)],
+        ),
+        FingerPattern::new(
+            "thumb 1 3",
+            E::Closed,
+            &[0, 1, 3],
+            &[(Q::Major, 2), (Q::Minor, 3)],
+        ),
+    ]
+};
+
+/// Every finger down in one closed position, for full-position cards.
+const CELLO_FULL_POSITION: [FingerPattern; 1] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [FingerPattern::new(
+        "1 2 3 4",
+        E::Closed,
+        &[1, 2, 3, 4],
+        &[(Q::Minor, 2), (Q::Major, 2), (Q::Minor, 3)],
+    )]
+};
+
+const VIOLIN_FULL_POSITION: [FingerPattern; 1] = {
+    use Extension as E;
+    use IntervalQuality as Q;
+    [FingerPattern::new(
+        "1 2 3 4",
+        E::Closed,
+        &[1, 2, 3, 4],
+        &[(Q::Major, 2), (Q::Minor, 3), (Q::Perfect, 4)],
+    )]
+};
+
+/// How the generator meant a card to be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingering {
+    /// The string under the first finger.
+    string: InstrumentString,
+    position: Position,
+    /// `None` for drills, which change shape as they go, and double stops.
+    pattern: Option<FingerPattern>,
+    /// The finger and string for each note, in the order the card shows them.
+    notes: Vec<(u8, InstrumentString)>,
+    /// The notes where the hand moves to a new position, and that position.
+    shifts: Vec<(usize, Position)>,
+}
+
+/// A natural harmonic: an open string touched lightly at one of its nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NaturalHarmonic {
+    string: InstrumentString,
+    node: HarmonicNode,
+}
+
+impl NaturalHarmonic {
+    /// The note written on the card, where the string is touched.
+    fn touched(self) -> Option<Note> {
+        self.string.open_note() + self.node.touched()
+    }
+
+    /// The pitch that sounds.
+    pub fn sounding(self) -> Option<Note> {
+        self.string.open_note() + self.node.sounding()
+    }
+}
+
+/// Where a string is touched for a natural harmonic, named after the
+/// interval above the open string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HarmonicNode {
+    Octave,
+    Fifth,
+    Fourth,
+    MajorThird,
+}
+
+impl HarmonicNode {
+    pub const ALL: [HarmonicNode; 4] = [
+        HarmonicNode::Octave,
+        HarmonicNode::Fifth,
+        HarmonicNode::Fourth,
+        HarmonicNode::MajorThird,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HarmonicNode::Octave => "octave",
+            HarmonicNode::Fifth => "fifth",
+            HarmonicNode::Fourth => "fourth",
+            HarmonicNode::MajorThird => "major third",
+        }
+    }
+
+    fn touched(self) -> Interval {
+        use IntervalQuality as Q;
+        match self {
+            HarmonicNode::Octave => Interval {
+                interval: 8,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::Fifth => Interval {
+                interval: 5,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::Fourth => Interval {
+                interval: 4,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::MajorThird => Interval {
+                interval: 3,
+                quality: Q::Major,
+            },
+        }
+    }
+
+    /// The 2nd, 3rd, 4th and 5th partials of the open string.
+    fn sounding(self) -> Interval {
+        use IntervalQuality as Q;
+        match self {
+            HarmonicNode::Octave => Interval {
+                interval: 8,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::Fifth => Interval {
+                interval: 12,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::Fourth => Interval {
+                interval: 15,
+                quality: Q::Perfect,
+            },
+            HarmonicNode::MajorThird => Interval {
+                interval: 17,
+                quality: Q::Major,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn positions() {
+    let position = |instrument: Instrument, string: &str, note: &str| {
+        instrument
+            .string_named(string)
+            .unwrap()
+            .position_of(note.parse().unwrap())
+    };
+    use Instrument::*;
+    assert_eq!(position(Cello, "A", "A#3"), Some(Position::Half));
+    assert_eq!(position(Cello, "A", "Bb3"), Some(Position::Half));
+    assert_eq!(position(Cello, "A", "Bbb3"), Some(Position::First));
+    assert_eq!(position(Cello, "A", "A3"), None);
+    assert_eq!(position(Cello, "D", "G3"), Some(Position::Third));
+    assert_eq!(position(Cello, "C", "C3"), Some(Position::Seventh));
+    assert_eq!(position(Cello, "C", "D3"), None);
+    assert_eq!(position(Violin, "E", "F#5"), Some(Position::First));
+    assert_eq!(position(Viola, "C", "G3"), Some(Position::Fourth));
+    assert_eq!(position(Bass, "E", "F2"), Some(Position::Half));
+
+    // the bass is tuned in fourths, the others in fifths
+    let string = Bass.string_named("E").unwrap();
+    assert_eq!(string.up(1), Bass.string_named("A"));
+    assert_eq!(string.up(3), Bass.string_named("G"));
+    assert_eq!(string.up(4), None);
+    assert_eq!(
+        (string.open_note() + Bass.string_offsets()[3]).unwrap(),
+        string.up(3).unwrap().open_note()
+    );
+    let string = Violin.string_named("G").unwrap();
+    assert_eq!(
+        (string.open_note() + Violin.string_offsets()[3]).unwrap(),
+        string.up(3).unwrap().open_note()
+    );
+
+    for instrument in Instrument::ALL {
+        assert_eq!(instrument.id().parse(), Ok(instrument));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn answer_reveal() {
+    let card: Card = "D3:D3:E3:F3".parse().unwrap();
+    assert_eq!(card.answer_svg().to_string(), card.svg().to_string());
+
+    let cards = CardGenerator::no_sharps_flats()
+        .card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    for card in cards {
+        let fingering = card.fingering().unwrap();
+        let answer = card.answer_svg().to_string();
+        assert!(answer.contains(&format!("{} position", fingering.position.label())));
+        assert!(answer.contains(&format!(
+            "finger pattern {}",
+            fingering.pattern.unwrap().id()
+        )));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn card_kinds() {
+    let answer = |card: &str| card.parse::<Card>().unwrap().answer_svg().to_string();
+    assert!(answer("F3:F#3").contains(">F#3</text>"));
+    assert!(answer("C4:C4:E4").contains(">major 3rd</text>"));
+    assert!(answer("C4:F4:B4").contains(">augmented 4th</text>"));
+    assert!(answer("C4:B3:F4").contains(">diminished 5th</text>"));
+    assert!(answer("C4:A4:G4:F#4:E4:D4").contains(">D major</text>"));
+    assert_eq!(
+        "C4:C4:D4:E4:F4:G4:A4"
+            .parse::<Card>()
+            .map(|card| card.kind()),
+        Ok(CardKind::Drill)
+    );
+    assert_eq!("C4".parse::<Card>(), Err(()));
+
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.single_note_cards_allowed = true;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    // the naturals from C2 to C5
+    assert_eq!(cards.len(), 22);
+    assert!(cards.iter().all(|card| card.kind() == CardKind::SingleNote));
+
+    settings.single_note_cards_allowed = false;
+    settings.full_position_cards_allowed = true;
+    settings.max_sharps = 2;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    for card in cards {
+        assert_eq!(card.kind(), CardKind::FullPosition);
+        assert_eq!(
+            card.fingering().unwrap().pattern.unwrap().fingers(),
+            [1, 2, 3, 4]
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn double_stops() {
+    let card: Card = "D3:D3+A3".parse().unwrap();
+    assert_eq!(card.kind(), CardKind::DoubleStop);
+    assert_eq!(card.id(), "D3:D3+A3");
+    // one stem for both notes, besides the five staff lines
+    assert_eq!(card.to_svg().matches("<line").count(), 6);
+    assert!(card
+        .answer_svg()
+        .to_string()
+        .contains(">perfect 5th</text>"));
+
+    // a second moves the lower head to the far side of a downward stem,
+    // and the accidentals are stacked
+    let svg = "C4:Eb4+Fb4".parse::<Card>().unwrap().to_svg();
+    assert!(svg.contains("M 133,91 ") && svg.contains("M 144,86 "));
+    assert!(svg.contains("m 122,67 ") && svg.contains("m 110,72 "));
+
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.double_stop_cards_allowed = true;
+    settings.max_card_count = None;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    for card in cards {
+        assert_eq!(card.kind(), CardKind::DoubleStop);
+        let fingering = card.fingering().unwrap();
+        let [(_, low), (_, high)] = fingering.notes[..] else {
+            panic!("{}", card.id());
+        };
+        assert_eq!(low.number(), high.number() + 1);
+        // a fifth between the strings, give or take what the hand spans
+        let span = card.notes[1].midi() - card.notes[0].midi();
+        assert!((3..=11).contains(&span), "{}", card.id());
+        assert!(card.answer_svg().to_string().contains(" strings, "));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn natural_harmonics() {
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.natural_harmonic_cards_allowed = true;
+    settings.max_card_count = None;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(cards
+        .iter()
+        .all(|card| card.kind() == CardKind::NaturalHarmonic));
+    let card = |id: &str| cards.iter().find(|card| card.id().ends_with(id)).unwrap();
+
+    let a_string = Instrument::Cello.string_named("A").unwrap();
+    let octave = card(":A4/A/harmonic");
+    let answer = octave.answer_svg().to_string();
+    assert!(
+        answer.contains(">A string, sounds A4</text>")
+            && answer.contains(">octave harmonic</text>")
+    );
+    assert!(octave
+        .to_svg()
+        .contains(r#"l 6,-5 6,5 -6,5 z" style="fill: none;"#));
+    assert!(octave.accepts(a_string, Position::Fourth));
+    assert_eq!(octave.instrument(), Instrument::Cello);
+
+    let answer = card(":G3/D/harmonic").answer_svg().to_string();
+    assert!(
+        answer.contains(">D string, sounds D5</text>")
+            && answer.contains(">fourth harmonic</text>")
+    );
+    let answer = card(":E2/C/harmonic").answer_svg().to_string();
+    assert!(answer.contains(">C string, sounds E4</text>"));
+}
+
+#[cfg(test)]
+#[test]
+fn artificial_harmonics() {
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.artificial_harmonic_cards_allowed = true;
+    settings.thumb_position_allowed = true;
+    settings.max_card_count = None;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    for card in &cards {
+        assert_eq!(card.kind(), CardKind::ArtificialHarmonic);
+        assert_eq!(
+            Interval::between(card.notes[0], card.notes[1]),
+            Interval::new(IntervalQuality::Perfect, 4).ok()
+        );
+        let fingering = card.fingering().unwrap();
+        assert_eq!(fingering.position, Position::Thumb);
+        assert_eq!(
+            fingering
+                .notes
+                .iter()
+                .map(|&(finger, _)| finger)
+                .collect::<Vec<_>>(),
+            [0, 3]
+        );
+        let svg = card.to_svg();
+        assert_eq!(svg.matches("fill: none;").count(), 1);
+        assert!(!svg.contains(">sounds "));
+    }
+    let card = cards
+        .iter()
+        .find(|card| card.id().starts_with("D3:E3:A3/"))
+        .unwrap();
+    assert!(card.answer_svg().to_string().contains(">sounds E5</text>"));
+
+    settings.sounding_pitch_shown = true;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(cards.iter().all(|card| card.to_svg().contains(">sounds ")));
+}
+
+#[cfg(test)]
+#[test]
+fn scale_drills() {
+    let mut settings = CardGenerator::no_sharps_flats();
+    settings.position_cards_allowed = false;
+    settings.scale_cards_allowed = true;
+    settings.arpeggio_cards_allowed = true;
+    settings.max_sharps = 2;
+    settings.scale_octaves = 2;
+    settings.max_card_count = None;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    let mut parts = cards.iter().peekable();
+    while let Some(card) = parts.next() {
+        let part = card.drill.unwrap();
+        assert!((6..=8).contains(&card.notes.len()));
+        assert!(card
+            .notes
+            .iter()
+            .all(|note| (36..=72).contains(&note.midi())));
+        // parts of one drill are dealt in order
+        if part.part < part.parts {
+            assert_eq!(parts.peek().unwrap().drill.unwrap().part, part.part + 1);
+        }
+        let fingering = card.fingering().unwrap();
+        assert_eq!(fingering.notes.len(), card.notes.len());
+        assert!(card.answer_svg().to_string().contains(&part.drill.name()));
+    }
+    assert!(!CardGenerator::scales()
+        .card_generator(&mut rand::thread_rng(), &Schedule::default())
+        .is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn extensions() {
+    let mut settings = CardGenerator::impossible();
+    settings.shuffled_order = false;
+    settings.string_count = 0;
+    settings.finger_patterns_allowed =
+        Instrument::Cello.patterns_named(&["3 & 4, backward extension"]);
+    settings.thumb_position_allowed = false;
+    settings.single_note_cards_allowed = false;
+    settings.interval_cards_allowed = false;
+    settings.full_position_cards_allowed = false;
+    settings.scale_fragment_cards_allowed = false;
+    settings.double_stop_cards_allowed = false;
+    settings.natural_harmonic_cards_allowed = false;
+    settings.artificial_harmonic_cards_allowed = false;
+    let cards = settings.card_generator(&mut rand::thread_rng(), &Schedule::default());
+    assert!(!cards.is_empty());
+    let a_string = Instrument::Cello.string_named("A").unwrap();
+    let mut found = false;
+    for card in cards {
+        let fingering = card.fingering().unwrap();
+        assert_eq!(fingering.pattern.unwrap().extension(), Extension::Backward);
+        assert!(card.answer_svg().to_string().contains("backward extension"));
+        // Bb3 C4 D4 on the A string: the first finger reaches back from 1st position
+        if card
+            .notes
+            .iter()
+            .map(|note| note.to_string())
+            .eq(["Bb3", "C4", "D4"])
+            && fingering.string == a_string
+        {
+            assert_eq!(fingering.position, Position::First);
+            assert!(card.accepts(a_string, Position::First));
+            assert!(card.accepts(a_string, Position::Half));
+            found = true;
+        }
+    }
+    assert!(found);
+}
+
+impl Clef {
+    /// The glyph drawn for the clef, and the note on the line it marks.
+    const fn glyph(self) -> (ClefGlyph, Note) {
+        use Clef as C;
+        use Letter as L;
+        let accidental = None;
+        match self {
+            C::Treble => (
+                ClefGlyph::G,
+                Note {
+                    letter: L::G,
+                    octave: 4,
+                    accidental,
+                },
+            ),
+            C::TrebleOctaveDown => (
+                ClefGlyph::G,
+                Note {
+                    letter: L::G,
+                    octave: 3,
+                    accidental,
+                },
+            ),
+            C::Soprano | C::Alto | C::Tenor | C::Baritone => (
+                ClefGlyph::C,
+                Note {
+                    letter: L::C,
+                    octave: 4,
+                    accidental,
+                },
+            ),
+            C::Bass => (
+                ClefGlyph::F,
+                Note {
+                    letter: L::F,
+                    octave: 3,
+                    accidental,
+                },
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ClefGlyph {
+    G,
+    C,
+    F,
+}
+
+impl ClefGlyph {
+    /// SVG path data with the glyph's top left corner at the origin.
+    const fn path(self) -> &'static str {
+        match self {
+            ClefGlyph::G => "m 12,5 c 3,0 3,3 3,7 0,12 -15,13 -15,26 0,8 7,11 11,11 4,0 11,-3 11,-8 0,-5 -3,-8 -7,-8 -10,0 -8,12 -3,12 -6,-6 -2,-9 2,-9 5,0 6,1 6,5 0,4 -5,6 -9,6 C 7,47 3,45 3,38 3,27 17,24 17,12 17,0 15,0 12,0 7,0 8,11 9,16 c 0,0 7,32 8,38 1,6 0,11 -4,11 -5,0 -5,-4 -5,-4 0,1 4,1 4,-2 0,-2 -1,-3 -3,-3 -2,0 -3,2 -3,3 0,4 3,7 7,7 4,0 6,-4 5,-12 L 10,16 C 9,13 9,5 12,5 Z",
+            ClefGlyph::C => "M 0,0 V 40 H 4 V 0 H 0 m 6,0 h 2 v 20 c 2,0 4,-6 5,-7 0,0 2,3 4,3 3,0 4,-2 4,-8 0,-6 -4,-6 -5,-6 -1,0 -2,0 -2,1 0,0 2,1 2,3 0,2 -2,2 -3,2 -2,0 -3,-1 -3,-3 0,-2 3,-5 6,-5 3,0 9,2 9,8 0,8 -6,10 -8,10 -2,0 -4,-1 -4,2 0,3 2,2 4,2 2,0 8,2 8,10 0,6 -6,8 -9,8 -3,0 -6,-3 -6,-5 0,-2 1,-3 3,-3 1,0 3,0 3,2 0,2 -2,3 -2,3 0,1 1,1 2,1 1,0 5,0 5,-6 0,-6 -1,-8 -4,-8 -2,0 -4,3 -4,3 -1,-1 -3,-7 -5,-7 V 40 H 6",
+            ClefGlyph::F => "m 7,7 c 3,0 5,2 5,4 0,2 -2,4 -5,4 C 3,15 1,13 1,9 1,5 5,0 12,0 17,0 25,3 25,12 25,28 0,40 0,39 9,33 19,24 19,12 19,8 18,2 11,2 4,2 3,9 3,9 3,9 4,7 7,7 Z M 28,5 a 2,2 0 1 0 4,0 a 2,2 0 1 0 -4,0 z M 28,15 a 2,2 0 1 0 4,0 a 2,2 0 1 0 -4,0 z",
+        }
+    }
+
+    /// How far below the top of the glyph the line it marks sits.
+    const fn line_offset(self) -> i32 {
+        match self {
+            ClefGlyph::G => 40,
+            ClefGlyph::C => 20,
+            ClefGlyph::F => 10,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn key_signatures() {
+    use Accidental::*;
+    let key: KeySignature = "2#".parse().unwrap();
+    assert_eq!(key.alteration(Letter::C), 1);
+    assert_eq!(key.alteration(Letter::G), 0);
+    let key: KeySignature = "3b".parse().unwrap();
+    assert_eq!(key.alteration(Letter::A), -1);
+    assert_eq!(key.alteration(Letter::D), 0);
+    let placements: Vec<_> = key
+        .placements(Clef::Bass)
+        .map(|(n, _)| n.to_string())
+        .collect();
+    assert_eq!(placements, ["B2", "E3", "A2"]);
+
+    let card: Card = "D3:F#3:F3:Bb3:2#".parse().unwrap();
+    assert_eq!(
+        card.displayed_accidentals(),
+        [None, Some(Natural), Some(Flat)]
+    );
+    let card: Card = "B4:F#4:G4:A4".parse().unwrap();
+    assert_eq!(card.best_key(3, 3), "1#".parse().unwrap());
+    assert_eq!(card.best_key(0, 3), KeySignature::default());
+}
+
+impl Accidental {
+    /// SVG path data for the accidental drawn in front of a notehead centered
+    /// on (`center_x`, `center_y`).
+    fn path(self, center_x: i32, center_y: i32) -> String {
+        use Accidental as A;
+        match self {
+            A::Sharp => format!("m {},{} -9,3 v -3 l 9,-3  m -3,-7 h 1 v 29 h -1  m -4,-28 h 1 v 30 h -1  m 7,-11 -9,3 v -3 l 9,-3", center_x-10, center_y-5),
+            A::Flat => format!("m {},{} c 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 2,0 3,2 3,4 0,2 -4,8 -7,8 -1,0 -1,-24 -1,-24 z m 6,16 c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z",center_x-17, center_y-18),
+            A::DoubleSharp => format!("m {},{} c 0,-2 -0,-4 1,-4 2,0 3,-1 4,-2 -1,-1 -2,-2 -4,-2 -1,0 -1,-2 -1,-4 v 0 c 2,0 4,-0 4,1 0,2 1,3 2,4 1,-1 2,-2 2,-4 0,-1 2,-1 4,-1 0,2 0,4 -1,4 -2,0 -3,1 -4,2 1,1 2,2 4,2 1,0 1,2 1,4 -2,0 -4,0 -4,-1 0,-2 -1,-3 -2,-4 -1,1 -2,2 -2,4 0,1 -2,1 -4,1 z", center_x-21, center_y+6),
+            A::DoubleFlat => format!("m {},{} c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z m 0,-16 c 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 2,0 3,2 3,4 0,2 -4,8 -7,8 0,0 -1,-0 -1,-4 0,0 -3,4 -5,4 -1,0 -1,-24 -1,-24 0,-1 2,0 2,0 v 13 c 0,0 1,-1 3,-1 l 1,-0 m 6,4 c 0,-2 -1,-2 -2,-2 -1,0 -2,2 -2,2 v 6 c 0,1 4,-3 4,-6 z", center_x-17, center_y-2),
+            A::Natural => format!("m {},{} h 1 v 22 h -1 z m 6,9 h 1 v 19 h -1 z m -6,2 l 7,-2 v 3 l -7,2 z m 0,8 l 7,-2 v 3 l -7,2 z", center_x-16, center_y-14),
+            // a single in front of the double, each starting from its own absolute point
+            A::TripleSharp => format!("{} M{}", A::Sharp.path(center_x - 12, center_y), &A::DoubleSharp.path(center_x, center_y)[1..]),
+            A::TripleFlat => format!("{} M{}", A::Flat.path(center_x - 14, center_y), &A::DoubleFlat.path(center_x, center_y)[1..]),
+        }
+    }
+}
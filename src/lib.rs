@@ -6,17 +6,57 @@ extern "C" {
     // `log(..)`
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: &str);
+    #[wasm_bindgen(js_namespace = console)]
+    fn debug(s: &str);
+}
+
+/// Sends `log` records to `console.debug`, which browsers hide unless asked
+/// for verbose output.
+struct ConsoleLogger;
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            debug(&format!(
+                "{} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+    }
+
+    fn flush(&self) {}
 }
 
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use web_sys::{Element, HtmlElement, HtmlInputElement, HtmlSelectElement, SvgsvgElement};
 
 mod cards;
-use cards::{status_card, Card, CelloCardGenerator, Note};
+mod quiz;
+mod scales;
+mod schedule;
+mod svg;
+pub mod theory;
+use cards::{answer_card, status_card, Card, Instrument, SettingsError};
+use quiz::{Answer, Quiz};
+use theory::Note;
+
+pub use cards::CardGenerator;
+pub use schedule::Schedule;
 
 const SVG_NAMESPACE: Option<&'static str> = Some("http://www.w3.org/2000/svg");
+const SCHEDULE_KEY: &str = "schedule";
 
 fn create_controls() {
     let document = web_sys::window().unwrap().document().unwrap();
@@ -75,6 +115,127 @@ fn create_controls() {
     div.replace_children_with_node_1(&button_svg);
 }
 
+/// Turns the displayed card over, between its question and answer sides.
+fn flip_card(card: &Card, revealed: &Cell<bool>) {
+    revealed.set(!revealed.get());
+    let card: Element = if revealed.get() {
+        answer_card(card)
+    } else {
+        card.into()
+    };
+    card.set_id("card");
+    let document = web_sys::window().unwrap().document().unwrap();
+    let div = document
+        .get_elements_by_class_name("main")
+        .get_with_index(0)
+        .unwrap();
+    div.replace_children_with_node_1(&card);
+}
+
+/// In quiz mode a card stays face down, and the deck can't move forward,
+/// until the card has been answered.
+fn card_locked(quiz: &Option<Quiz>, index: usize) -> bool {
+    quiz.as_ref().is_some_and(|quiz| !quiz.is_answered(index))
+}
+
+fn load_schedule() -> Schedule {
+    web_sys::window()
+        .unwrap()
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(SCHEDULE_KEY).ok().flatten())
+        .and_then(|text| text.parse().ok())
+        .unwrap_or_default()
+}
+
+fn save_schedule(schedule: &Schedule) {
+    if let Ok(Some(storage)) = web_sys::window().unwrap().local_storage() {
+        if storage
+            .set_item(SCHEDULE_KEY, &schedule.to_string())
+            .is_err()
+        {
+            log("could not save the schedule");
+        }
+    }
+}
+
+/// Puts the settings in the address bar, so the page can be bookmarked or sent
+/// to someone else to get the same kind of deck.
+fn share_settings(settings: &CardGenerator) {
+    let url = format!("?{}", settings.to_query());
+    let updated = web_sys::window()
+        .unwrap()
+        .history()
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)));
+    if updated.is_err() {
+        log("could not update the address bar");
+    }
+}
+
+/// Marks the answer selected in the quiz panel and turns the card over to show
+/// the expected one. The first answer for each card is graded in the schedule.
+fn check_answer(
+    card: &Card,
+    index: usize,
+    quiz: &mut Quiz,
+    schedule: &mut Schedule,
+    revealed: &Cell<bool>,
+) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let selected = |element_id| {
+        document
+            .get_element_by_id(element_id)
+            .unwrap()
+            .dyn_into::<HtmlSelectElement>()
+            .unwrap()
+            .value()
+    };
+    let (Some(string), Ok(position)) = (
+        card.instrument().string_named(&selected("quiz_string")),
+        selected("quiz_position").parse(),
+    ) else {
+        return;
+    };
+    let first_attempt = !quiz.is_answered(index);
+    let correct = quiz.record(index, Answer { string, position }.is_correct(card));
+    if first_attempt {
+        schedule.grade(card, correct);
+        save_schedule(schedule);
+    }
+    revealed.set(false);
+    flip_card(card, revealed);
+}
+
+/// Shows the quiz panel when quiz mode is on, with the result for the current
+/// card and the score so far.
+fn update_quiz_panel(quiz: &Option<Quiz>, index: usize) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let panel = document
+        .get_element_by_id("quiz")
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap();
+    let Some(quiz) = quiz else {
+        panel.style().set_property("display", "none").unwrap();
+        return;
+    };
+    panel.style().set_property("display", "flex").unwrap();
+    let feedback = match quiz.result(index) {
+        Some(true) => "Correct!",
+        Some(false) => "Not quite, the answer is on the card",
+        None => "",
+    };
+    document
+        .get_element_by_id("quiz_feedback")
+        .unwrap()
+        .set_inner_html(feedback);
+    document
+        .get_element_by_id("quiz_score")
+        .unwrap()
+        .set_inner_html(&quiz.to_string());
+}
+
 macro_rules! update_x_notes_fn {
     ($fn_name:ident,$name:literal) => {
         #[wasm_bindgen]
@@ -97,12 +258,16 @@ macro_rules! update_x_notes_fn {
                 .value();
             let min = min
                 .parse::<u8>()
-                .map(|m| Note::from_midi(m).to_string())
-                .or(min.parse::<Note>().map(|n| n.to_string()));
+                .ok()
+                .and_then(Note::from_midi)
+                .or(min.parse::<Note>().ok())
+                .map(|n| n.to_string());
             let max = max
                 .parse::<u8>()
-                .map(|m| Note::from_midi(m).to_string())
-                .or(max.parse::<Note>().map(|n| n.to_string()));
+                .ok()
+                .and_then(Note::from_midi)
+                .or(max.parse::<Note>().ok())
+                .map(|n| n.to_string());
             let min = min.as_deref().unwrap_or("Not a note");
             let max = max.as_deref().unwrap_or("Not a note");
 
@@ -114,42 +279,207 @@ update_x_notes_fn!(update_bass_notes, "bass");
 update_x_notes_fn!(update_treble_notes, "treble");
 update_x_notes_fn!(update_tenor_notes, "tenor");
 update_x_notes_fn!(update_alto_notes, "alto");
+update_x_notes_fn!(update_treble_8vb_notes, "treble_8vb");
+update_x_notes_fn!(update_soprano_notes, "soprano");
+update_x_notes_fn!(update_baritone_notes, "baritone");
+
+/// Names the string and finger pattern switches in the settings, and the
+/// strings offered in the quiz, after the instrument.
+fn show_instrument(instrument: Instrument) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    for string in instrument.strings() {
+        document
+            .get_element_by_id(&format!("string_{}_name", string.number()))
+            .unwrap()
+            .set_inner_html(&format!("{} String", string.name()));
+    }
+    let patterns = instrument.finger_patterns();
+    for number in 1..=5 {
+        let label = document
+            .get_element_by_id(&format!("finger_pattern_{}_label", number))
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+        // instruments with fewer patterns hide the switches they don't use
+        match patterns.get(number - 1) {
+            Some(pattern) => {
+                label.style().set_property("display", "inline").unwrap();
+                document
+                    .get_element_by_id(&format!("finger_pattern_{}_name", number))
+                    .unwrap()
+                    .set_inner_html(&pattern.name());
+            }
+            None => label.style().set_property("display", "none").unwrap(),
+        }
+    }
+    let options: String = instrument
+        .strings()
+        .map(|string| format!(r#"<option value="{0}">{0}</option>"#, string.name()))
+        .collect();
+    document
+        .get_element_by_id("quiz_string")
+        .unwrap()
+        .set_inner_html(&options);
+}
+
+#[wasm_bindgen]
+pub fn update_instrument_labels() {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let instrument = document
+        .get_element_by_id("instrument")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value()
+        .parse()
+        .unwrap_or(Instrument::Cello);
+    show_instrument(instrument);
+}
+
+/// Marks each error next to its input in the menu, clearing any earlier marks.
+fn mark_settings_errors(errors: &[SettingsError]) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let marks = document.get_elements_by_class_name("settings_error");
+    while let Some(mark) = marks.get_with_index(0) {
+        mark.remove();
+    }
+    for error in errors {
+        document
+            .get_element_by_id(error.field())
+            .unwrap()
+            .insert_adjacent_html(
+                "afterend",
+                &format!(
+                    "<span class=\"settings_error\">{}</span>",
+                    error.explanation()
+                ),
+            )
+            .unwrap();
+    }
+}
+
+/// Sets how much goes to `console.debug`, from "off" through "error",
+/// "warn", "info" and "debug" to "trace".
+#[wasm_bindgen]
+pub fn set_log_level(level: &str) {
+    match level.parse() {
+        Ok(level) => log::set_max_level(level),
+        Err(_) => log(&format!("unknown log level {:?}", level)),
+    }
+}
+
+/// Shows how many cards the settings in the menu make and, when there are
+/// none, why not.
+#[wasm_bindgen]
+pub fn update_deck_size() {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let mut settings = match CardGenerator::read_settings() {
+        Ok(settings) => settings,
+        Err(errors) => {
+            mark_settings_errors(&errors);
+            document
+                .get_element_by_id("deck_size")
+                .unwrap()
+                .set_inner_html("Fix the marked settings to see how many cards they make.");
+            return;
+        }
+    };
+    mark_settings_errors(&[]);
+    // pinned in the form too, so closing the menu deals the deck counted here
+    let seed = settings.pin_seed();
+    document
+        .get_element_by_id("seed")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .set_value(&seed.to_string());
+    let text = match settings.empty_deck_reason() {
+        Some(reason) => format!("No cards match these settings. {}", reason.explanation()),
+        None => match settings.card_count(&mut ChaCha8Rng::seed_from_u64(seed)) {
+            1 => "1 card matches these settings.".to_string(),
+            count => format!("{} cards match these settings.", count),
+        },
+    };
+    let text = match settings.unspellable_card_count() {
+        0 => text,
+        1 => format!("{} 1 more needs a triple sharp or flat.", text),
+        dropped => format!("{} {} more need triple sharps or flats.", text, dropped),
+    };
+    document
+        .get_element_by_id("deck_size")
+        .unwrap()
+        .set_inner_html(&text);
+}
+
 #[wasm_bindgen]
 pub fn load_preset() {
     let document = web_sys::window().unwrap().document().unwrap();
     let preset = document.get_element_by_id("presets").unwrap().dyn_into::<HtmlSelectElement>().unwrap().value();
     match preset.as_str() {
-        "no_sharps_flats" => CelloCardGenerator::no_sharps_flats().write_settings(),
-        "one_flat" => CelloCardGenerator::one_flat().write_settings(),
-        "one_sharp" => CelloCardGenerator::one_sharp().write_settings(),
-        "two_flats" => CelloCardGenerator::two_flats().write_settings(),
-        "two_sharps" => CelloCardGenerator::two_sharps().write_settings(),
-        "three_flats" => CelloCardGenerator::three_flats().write_settings(),
-        "three_sharps" => CelloCardGenerator::three_sharps().write_settings(),
-        "tenor_clef_initial" => CelloCardGenerator::tenor_clef_initial().write_settings(),
-        "tenor_clef_advanced" => CelloCardGenerator::tenor_clef_advanced().write_settings(),
-        "treble_clef_initial" => CelloCardGenerator::treble_clef_initial().write_settings(),
-        "treble_clef_advanced" => CelloCardGenerator::treble_clef_advanced().write_settings(),
-        "advanced" => CelloCardGenerator::advanced().write_settings(),
-        "impossible" => CelloCardGenerator::impossible().write_settings(),
-        _ => {return;}
+        "no_sharps_flats" => CardGenerator::no_sharps_flats().write_settings(),
+        "one_flat" => CardGenerator::one_flat().write_settings(),
+        "one_sharp" => CardGenerator::one_sharp().write_settings(),
+        "two_flats" => CardGenerator::two_flats().write_settings(),
+        "two_sharps" => CardGenerator::two_sharps().write_settings(),
+        "three_flats" => CardGenerator::three_flats().write_settings(),
+        "three_sharps" => CardGenerator::three_sharps().write_settings(),
+        "tenor_clef_initial" => CardGenerator::tenor_clef_initial().write_settings(),
+        "tenor_clef_advanced" => CardGenerator::tenor_clef_advanced().write_settings(),
+        "treble_clef_initial" => CardGenerator::treble_clef_initial().write_settings(),
+        "treble_clef_advanced" => CardGenerator::treble_clef_advanced().write_settings(),
+        "advanced" => CardGenerator::advanced().write_settings(),
+        "impossible" => CardGenerator::impossible().write_settings(),
+        "scales" => CardGenerator::scales().write_settings(),
+        "thumb_position" => CardGenerator::thumb_position().write_settings(),
+        "thumb_position_advanced" => CardGenerator::thumb_position_advanced().write_settings(),
+        "violin" => CardGenerator::violin().write_settings(),
+        "viola" => CardGenerator::viola().write_settings(),
+        "double_bass" => CardGenerator::double_bass().write_settings(),
+        _ => {}
     }
 }
 
 #[wasm_bindgen]
 pub fn main() {
     console_error_panic_hook::set_once();
+    // diagnostics are for development; raise the level from the console with `set_log_level`
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(if cfg!(debug_assertions) {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Off
+        });
+    }
     let document = web_sys::window().unwrap().document().unwrap();
 
-    use rand::thread_rng;
-    let rng = Rc::new(RefCell::new(thread_rng()));
+    // settings shared in a link take the place of the default preset
+    let query = web_sys::window()
+        .unwrap()
+        .location()
+        .search()
+        .unwrap_or_default();
+    let current_settings = Rc::new(RefCell::new(
+        CardGenerator::from_query(&query).unwrap_or_else(|_| CardGenerator::no_sharps_flats()),
+    ));
 
-    let current_settings = Rc::new(RefCell::new(CelloCardGenerator::no_sharps_flats()));
 
+    let schedule = Rc::new(RefCell::new(load_schedule()));
+    schedule.borrow_mut().start_session();
+    save_schedule(&schedule.borrow());
 
-    let cards = current_settings.borrow().card_generator(&mut *rng.borrow_mut());
+    let seed = current_settings.borrow_mut().pin_seed();
+    let cards = current_settings
+        .borrow()
+        .card_generator(&mut ChaCha8Rng::seed_from_u64(seed), &schedule.borrow());
     let cards: Rc<RefCell<Vec<Card>>> = Rc::new(RefCell::new(cards));
     let card_index = Rc::new(Cell::new(0usize));
+    let revealed = Rc::new(Cell::new(false));
+    let quiz = Rc::new(RefCell::new(
+        current_settings
+            .borrow()
+            .quiz_mode()
+            .then(|| Quiz::new(cards.borrow().len())),
+    ));
 
     {
         let cards = cards.borrow();
@@ -175,6 +505,8 @@ pub fn main() {
             card_index.get() + 1,
             cards.borrow().len()
         ));
+    update_quiz_panel(&quiz.borrow(), card_index.get());
+    show_instrument(current_settings.borrow().instrument());
 
     let right_arrow = document.get_element_by_id("right_arrow").unwrap();
     let right_pressed = Rc::new(Cell::new(false));
@@ -191,12 +523,16 @@ pub fn main() {
     {
         let cards = Rc::clone(&cards);
         let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
         let right_pressed = Rc::clone(&right_pressed);
         let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
             let cards = cards.borrow();
-            if right_pressed.get() {
+            if right_pressed.get() && !card_locked(&quiz.borrow(), card_index.get()) {
                 let new_index = (card_index.get()+1).min(cards.len() - 1);
                 card_index.set(new_index);
+                revealed.set(false);
+                update_quiz_panel(&quiz.borrow(), card_index.get());
                 if let Some(card) = cards.get(new_index){
                     let card: Element = card.into();
                     card.set_id("card");
@@ -212,8 +548,8 @@ pub fn main() {
                         .set_inner_html(&format!("{}/{}", card_index.get() + 1, cards.len()));
                     div.replace_children_with_node_1(&card);
                 }
-                right_pressed.set(false);
             }
+            right_pressed.set(false);
         });
         right_arrow
             .add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())
@@ -235,12 +571,16 @@ pub fn main() {
     {
         let cards = Rc::clone(&cards);
         let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
         let left_pressed = Rc::clone(&left_pressed);
         let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
             let cards = cards.borrow();
             if left_pressed.get() {
-                let new_index = card_index.get().checked_sub(1).unwrap_or(0);
+                let new_index = card_index.get().saturating_sub(1);
                 card_index.set(new_index);
+                revealed.set(false);
+                update_quiz_panel(&quiz.borrow(), card_index.get());
                 if let Some(card) = cards.get(card_index.get()) {
                     let card: Element = card.into();
                     card.set_id("card");
@@ -266,12 +606,17 @@ pub fn main() {
     {
         let cards = Rc::clone(&cards);
         let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
+        let schedule = Rc::clone(&schedule);
         let closure = Closure::<dyn FnMut(_)>::new(move |event: web_sys::KeyboardEvent| {
             let cards = cards.borrow();
             match event.key().as_str(){
                 "ArrowLeft" => {
-                    let new_index = card_index.get().checked_sub(1).unwrap_or(0);
+                    let new_index = card_index.get().saturating_sub(1);
                     card_index.set(new_index);
+                    revealed.set(false);
+                    update_quiz_panel(&quiz.borrow(), card_index.get());
                     if let Some(card) = cards.get(card_index.get()) {
                         let card: Element = card.into();
                         card.set_id("card");
@@ -287,9 +632,11 @@ pub fn main() {
                         div.replace_children_with_node_1(&card);
                     }
                 },
-                "ArrowRight" => {
+                "ArrowRight" if !card_locked(&quiz.borrow(), card_index.get()) => {
                     let new_index = (card_index.get()+1).min(cards.len() - 1);
                     card_index.set(new_index);
+                    revealed.set(false);
+                    update_quiz_panel(&quiz.borrow(), card_index.get());
                     if let Some(card) = cards.get(new_index){
                         let card: Element = card.into();
                         card.set_id("card");
@@ -306,12 +653,79 @@ pub fn main() {
                         div.replace_children_with_node_1(&card);
                     }
                 },
+                " " | "Enter" => {
+                    if let Some(card) = cards.get(card_index.get()) {
+                        let mut quiz = quiz.borrow_mut();
+                        match quiz.as_mut() {
+                            Some(quiz) if !quiz.is_answered(card_index.get()) => {
+                                check_answer(
+                                    card,
+                                    card_index.get(),
+                                    quiz,
+                                    &mut schedule.borrow_mut(),
+                                    &revealed,
+                                );
+                            }
+                            _ => flip_card(card, &revealed),
+                        }
+                    }
+                    update_quiz_panel(&quiz.borrow(), card_index.get());
+                }
                 _ => {}
             }
         });
         web_sys::window().unwrap().add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref()).unwrap();
         closure.forget();
     }
+    {
+        let cards = Rc::clone(&cards);
+        let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
+        let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
+            if card_locked(&quiz.borrow(), card_index.get()) {
+                return;
+            }
+            if let Some(card) = cards.borrow().get(card_index.get()) {
+                flip_card(card, &revealed);
+            }
+        });
+        document
+            .get_elements_by_class_name("main")
+            .get_with_index(0)
+            .unwrap()
+            .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+            .unwrap();
+        closure.forget();
+    }
+    {
+        let cards = Rc::clone(&cards);
+        let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
+        let schedule = Rc::clone(&schedule);
+        let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
+            if let (Some(card), Some(quiz)) = (
+                cards.borrow().get(card_index.get()),
+                quiz.borrow_mut().as_mut(),
+            ) {
+                check_answer(
+                    card,
+                    card_index.get(),
+                    quiz,
+                    &mut schedule.borrow_mut(),
+                    &revealed,
+                );
+            }
+            update_quiz_panel(&quiz.borrow(), card_index.get());
+        });
+        document
+            .get_element_by_id("quiz_check")
+            .unwrap()
+            .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+            .unwrap();
+        closure.forget();
+    }
 
     let menu_icon = document.get_element_by_id("menu-icon").unwrap();
 
@@ -328,10 +742,12 @@ pub fn main() {
         closure.forget();
     }
     {
-        let rng = Rc::clone(&rng);
         let current_settings = Rc::clone(&current_settings);
 
         let card_index = Rc::clone(&card_index);
+        let revealed = Rc::clone(&revealed);
+        let quiz = Rc::clone(&quiz);
+        let schedule = Rc::clone(&schedule);
         let cards = Rc::clone(&cards);
         let menu_toggled = Rc::clone(&menu_toggled);
         let menu_pressed = Rc::clone(&menu_pressed);
@@ -351,17 +767,38 @@ pub fn main() {
                         full_screen_menu.style().set_property(name, "0").unwrap();
                     }
                     current_settings.borrow().write_settings();
+                    update_deck_size();
                 } else {
+                    let mut new_settings = match CardGenerator::read_settings() {
+                        Ok(settings) => settings,
+                        Err(errors) => {
+                            // Stay in the menu until the marked inputs are fixed.
+                            mark_settings_errors(&errors);
+                            menu_toggled.set(true);
+                            return;
+                        }
+                    };
                     log("updated settings");
                     for (name, value) in [("right", "100%"), ("bottom", "100%")] {
                         full_screen_menu.style().set_property(name, value).unwrap();
                     }
-                    let new_settings = CelloCardGenerator::read_settings();
-                    if &new_settings != &*current_settings.borrow() {
-                        let new_cards = new_settings.card_generator(&mut *rng.borrow_mut());
-                        let card: Element = new_cards.get(0).map(|c|c.into()).unwrap_or_else(|| status_card("Looks like there's no cards in this deck! <br> Try adjusting some options or using a preset."));
+                    if new_settings != *current_settings.borrow() {
+                        schedule.borrow_mut().start_session();
+                        save_schedule(&schedule.borrow());
+                        let seed = new_settings.pin_seed();
+                        let new_cards = new_settings.card_generator(
+                            &mut ChaCha8Rng::seed_from_u64(seed),
+                            &schedule.borrow(),
+                        );
+                        let card: Element = new_cards.first().map(|c|c.into()).unwrap_or_else(|| {
+                            let reason = new_settings.empty_deck_reason().map_or("", |reason| reason.explanation());
+                            status_card(&format!("Looks like there's no cards in this deck! <br> {} <br> Try adjusting some options or using a preset.", reason))
+                        });
+                        quiz.replace(new_settings.quiz_mode().then(|| Quiz::new(new_cards.len())));
                         cards.replace(new_cards);
                         card_index.set(0);
+                        revealed.set(false);
+                        update_quiz_panel(&quiz.borrow(), card_index.get());
                         card.set_id("card");
                         let document = web_sys::window().unwrap().document().unwrap();
                         let div = document
@@ -380,6 +817,7 @@ pub fn main() {
 
                         current_settings.replace(new_settings);
                     }
+                    share_settings(&current_settings.borrow());
                 }
             }
         });
@@ -399,6 +837,27 @@ pub fn main() {
             .unwrap();
         closure.forget();
     };
+    {
+        // every switch in the menu changes the deck, presets included
+        let closure = Closure::<dyn Fn()>::new(update_deck_size);
+        let function = &closure.as_ref().unchecked_ref();
+        document
+            .get_element_by_id("full-screen-menu")
+            .unwrap()
+            .add_event_listener_with_callback("change", function)
+            .unwrap();
+        closure.forget();
+    };
+    {
+        let closure = Closure::<dyn Fn()>::new(update_instrument_labels);
+        let function = &closure.as_ref().unchecked_ref();
+        document
+            .get_element_by_id("instrument")
+            .unwrap()
+            .add_event_listener_with_callback("change", function)
+            .unwrap();
+        closure.forget();
+    };
 
     macro_rules! update_x_notes {
         ($fn_name:ident,$name:literal) => {
@@ -421,6 +880,9 @@ pub fn main() {
     update_x_notes!(update_treble_notes, "treble");
     update_x_notes!(update_tenor_notes, "tenor");
     update_x_notes!(update_alto_notes, "alto");
+    update_x_notes!(update_treble_8vb_notes, "treble_8vb");
+    update_x_notes!(update_soprano_notes, "soprano");
+    update_x_notes!(update_baritone_notes, "baritone");
 
     log("complete");
 }
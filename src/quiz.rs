@@ -0,0 +1,99 @@
+use std::fmt::Display;
+
+use crate::cards::{Card, InstrumentString, Position};
+
+/// What the student thinks the card is: the string under the first finger and
+/// the position of the hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Answer {
+    pub string: InstrumentString,
+    pub position: Position,
+}
+
+impl Answer {
+    pub fn is_correct(self, card: &Card) -> bool {
+        card.accepts(self.string, self.position)
+    }
+}
+
+/// Running results for a deck in quiz mode, one slot per card.
+#[derive(Debug, Clone, Default)]
+pub struct Quiz {
+    results: Vec<Option<bool>>,
+}
+
+impl Quiz {
+    pub fn new(card_count: usize) -> Self {
+        Quiz {
+            results: vec![None; card_count],
+        }
+    }
+
+    /// Marks the card at `index`. Only the first attempt counts towards the
+    /// score, so checking again after seeing the answer changes nothing.
+    pub fn record(&mut self, index: usize, correct: bool) -> bool {
+        match self.results.get_mut(index) {
+            Some(result @ None) => {
+                *result = Some(correct);
+                correct
+            }
+            Some(Some(first)) => *first,
+            None => false,
+        }
+    }
+
+    pub fn result(&self, index: usize) -> Option<bool> {
+        self.results.get(index).copied().flatten()
+    }
+
+    pub fn is_answered(&self, index: usize) -> bool {
+        self.result(index).is_some()
+    }
+
+    pub fn correct(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|&&result| result == Some(true))
+            .count()
+    }
+
+    pub fn answered(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.is_some())
+            .count()
+    }
+}
+
+impl Display for Quiz {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Score: {}/{}", self.correct(), self.answered())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn quiz_scoring() {
+    use crate::cards::Instrument;
+    let card: Card = "C4:D4:E4:F4".parse().unwrap();
+    let answer = |string: &str, position: &str| Answer {
+        string: Instrument::Cello.string_named(string).unwrap(),
+        position: position.parse().unwrap(),
+    };
+    // D4 is 3rd position on the A string and 7th on the D string
+    assert!(answer("A", "3rd").is_correct(&card));
+    assert!(answer("D", "7th").is_correct(&card));
+    assert!(!answer("D", "3rd").is_correct(&card));
+    assert!(!answer("C", "3rd").is_correct(&card));
+
+    let shuffled: Card = "C4:E4:D4:F4".parse().unwrap();
+    assert!(answer("A", "3rd").is_correct(&shuffled));
+
+    let mut quiz = Quiz::new(3);
+    assert!(!quiz.record(0, false));
+    assert!(!quiz.record(0, true));
+    assert!(quiz.record(2, true));
+    assert_eq!(quiz.result(1), None);
+    assert!(!quiz.is_answered(1));
+    assert_eq!(quiz.to_string(), "Score: 1/2");
+}
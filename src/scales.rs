@@ -0,0 +1,393 @@
+use crate::cards::{Instrument, InstrumentString, Position};
+use crate::theory::{Interval, IntervalQuality, Note};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    /// Raised sixth and seventh going up, natural minor coming down.
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+}
+
+impl Scale {
+    pub const ALL: [Scale; 9] = {
+        use Scale::*;
+        [
+            Major,
+            NaturalMinor,
+            HarmonicMinor,
+            MelodicMinor,
+            Dorian,
+            Phrygian,
+            Lydian,
+            Mixolydian,
+            Locrian,
+        ]
+    };
+
+    pub fn name(self) -> &'static str {
+        use Scale as S;
+        match self {
+            S::Major => "major",
+            S::NaturalMinor => "natural minor",
+            S::HarmonicMinor => "harmonic minor",
+            S::MelodicMinor => "melodic minor",
+            S::Dorian => "dorian",
+            S::Phrygian => "phrygian",
+            S::Lydian => "lydian",
+            S::Mixolydian => "mixolydian",
+            S::Locrian => "locrian",
+        }
+    }
+
+    pub fn is_minor(self) -> bool {
+        matches!(
+            self,
+            Scale::NaturalMinor | Scale::HarmonicMinor | Scale::MelodicMinor
+        )
+    }
+
+    /// The church modes other than major (ionian) and natural minor (aeolian).
+    pub fn is_mode(self) -> bool {
+        !self.is_minor() && self != Scale::Major
+    }
+
+    /// Each degree above the tonic on the way up.
+    fn ascending(self) -> [(IntervalQuality, i8); 7] {
+        use IntervalQuality as Q;
+        use Scale as S;
+        let [second, third, fourth, fifth, sixth, seventh] = match self {
+            S::Major => [
+                Q::Major,
+                Q::Major,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Major,
+                Q::Major,
+            ],
+            S::NaturalMinor => [
+                Q::Major,
+                Q::Minor,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Minor,
+                Q::Minor,
+            ],
+            S::HarmonicMinor => [
+                Q::Major,
+                Q::Minor,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Minor,
+                Q::Major,
+            ],
+            S::MelodicMinor => [
+                Q::Major,
+                Q::Minor,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Major,
+                Q::Major,
+            ],
+            S::Dorian => [
+                Q::Major,
+                Q::Minor,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Major,
+                Q::Minor,
+            ],
+            S::Phrygian => [
+                Q::Minor,
+                Q::Minor,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Minor,
+                Q::Minor,
+            ],
+            S::Lydian => [
+                Q::Major,
+                Q::Major,
+                Q::Augmented,
+                Q::Perfect,
+                Q::Major,
+                Q::Major,
+            ],
+            S::Mixolydian => [
+                Q::Major,
+                Q::Major,
+                Q::Perfect,
+                Q::Perfect,
+                Q::Major,
+                Q::Minor,
+            ],
+            S::Locrian => [
+                Q::Minor,
+                Q::Minor,
+                Q::Perfect,
+                Q::Diminished,
+                Q::Minor,
+                Q::Minor,
+            ],
+        };
+        [
+            (Q::Perfect, 1),
+            (second, 2),
+            (third, 3),
+            (fourth, 4),
+            (fifth, 5),
+            (sixth, 6),
+            (seventh, 7),
+        ]
+    }
+
+    /// Each degree above the tonic on the way down.
+    fn descending(self) -> [(IntervalQuality, i8); 7] {
+        match self {
+            Scale::MelodicMinor => Scale::NaturalMinor.ascending(),
+            scale => scale.ascending(),
+        }
+    }
+}
+
+/// A scale or arpeggio over one or more octaves, up from the tonic and back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Drill {
+    pub tonic: Note,
+    pub scale: Scale,
+    /// Only the first, third and fifth degrees.
+    pub arpeggio: bool,
+    pub octaves: u8,
+}
+
+impl Drill {
+    /// Every note of the drill in playing order, or `None` if one can't be
+    /// spelled.
+    pub fn notes(self) -> Option<Vec<Note>> {
+        let degrees: &[usize] = if self.arpeggio {
+            &[0, 2, 4]
+        } else {
+            &[0, 1, 2, 3, 4, 5, 6]
+        };
+        let note = |steps: [(IntervalQuality, i8); 7], octave: u8, degree: usize| {
+            let (quality, interval) = steps[degree];
+            self.tonic + Interval::new(quality, interval + octave as i8 * 7).ok()?
+        };
+        let up = (0..self.octaves)
+            .flat_map(|octave| degrees.iter().map(move |&degree| (octave, degree)));
+        let top = note(self.scale.ascending(), self.octaves, 0);
+        let down = (0..self.octaves)
+            .rev()
+            .flat_map(|octave| degrees.iter().rev().map(move |&degree| (octave, degree)));
+        up.map(|(octave, degree)| note(self.scale.ascending(), octave, degree))
+            .chain(std::iter::once(top))
+            .chain(down.map(|(octave, degree)| note(self.scale.descending(), octave, degree)))
+            .collect()
+    }
+
+    /// e.g. "D harmonic minor scale, 2 octaves".
+    pub fn name(self) -> String {
+        format!(
+            "{} {} {}, {} octave{}",
+            self.tonic.name(),
+            self.scale.name(),
+            if self.arpeggio { "arpeggio" } else { "scale" },
+            self.octaves,
+            if self.octaves == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Where one note of a drill is played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stop {
+    pub finger: u8,
+    pub string: InstrumentString,
+    pub position: Position,
+}
+
+/// A hand placement on one string: the first finger (or thumb) `hand`
+/// semitones above the open string, in `position`.
+#[derive(Debug, Clone, Copy)]
+struct Hand {
+    string: InstrumentString,
+    hand: u8,
+    thumb: bool,
+    position: Position,
+    finger: u8,
+}
+
+/// The fingers a hand can reach, as (finger, semitones above the first
+/// finger), and the highest hand placement before the next kind of hand is
+/// needed.
+fn reach(instrument: Instrument, thumb: bool) -> (&'static [(u8, u8)], u8) {
+    use Instrument as I;
+    match (instrument, thumb) {
+        (I::Cello, false) => (&[(1, 0), (2, 1), (3, 2), (4, 3)], 11),
+        (I::Violin | I::Viola, false) => (&[(1, 0), (2, 1), (2, 2), (3, 3), (3, 4), (4, 5)], 16),
+        (I::Bass, false) => (&[(1, 0), (2, 1), (4, 2)], 11),
+        (I::Cello | I::Bass, true) => (&[(0, 0), (1, 1), (1, 2), (2, 3), (2, 4), (3, 5)], 24),
+        (I::Violin | I::Viola, true) => (&[], 0),
+    }
+}
+
+/// Suggests a fingering for `notes` using only `strings`, with as few and
+/// as small shifts as it can, crossing strings in the direction the line is
+/// moving. Only stopped notes are used, so the fingering moves to any key.
+/// `None` if some note can't be reached at all.
+pub fn fingering(notes: &[Note], strings: &[InstrumentString]) -> Option<Vec<Stop>> {
+    let hands = |note: Note| -> Vec<Hand> {
+        let mut hands = Vec::new();
+        for &string in strings {
+            let above = note.midi() as i16 - string.open_note().midi() as i16;
+            for thumb in [false, true] {
+                let (fingers, highest) = reach(string.instrument(), thumb);
+                let lowest = if thumb { 12 } else { 1 };
+                for &(finger, offset) in fingers {
+                    let hand = above - offset as i16;
+                    if !(lowest..=highest as i16).contains(&hand) {
+                        continue;
+                    }
+                    // violin hands reach higher than the positions have names
+                    let position = if thumb {
+                        Some(Position::Thumb)
+                    } else {
+                        Note::from_midi(string.open_note().midi() + hand as u8)
+                            .and_then(|hand| string.position_of(hand))
+                    };
+                    if let Some(position) = position {
+                        hands.push(Hand {
+                            string,
+                            hand: hand as u8,
+                            thumb,
+                            position,
+                            finger,
+                        });
+                    }
+                }
+            }
+        }
+        hands
+    };
+    let cost = |from: &Hand, to: &Hand, rising: bool| {
+        let mut cost = if to.thumb { 4 } else { 0 };
+        if (from.hand, from.thumb) != (to.hand, to.thumb) {
+            cost += 2 + from.hand.abs_diff(to.hand) as u32;
+            if (to.hand > from.hand) != rising {
+                cost += 4;
+            }
+        }
+        if from.string != to.string {
+            // strings are numbered from the highest
+            cost += from.string.number().abs_diff(to.string.number()) as u32;
+            if (to.string.number() < from.string.number()) != rising {
+                cost += 4;
+            }
+        }
+        cost
+    };
+
+    // cheapest way to reach each hand for the current note, and how
+    let mut steps: Vec<Vec<(Hand, u32, usize)>> = Vec::with_capacity(notes.len());
+    for (i, &note) in notes.iter().enumerate() {
+        let candidates = hands(note);
+        let step = match steps.last() {
+            None => candidates.into_iter().map(|hand| (hand, 0, 0)).collect(),
+            Some(previous) => {
+                let rising = note.midi() >= notes[i - 1].midi();
+                candidates
+                    .into_iter()
+                    .filter_map(|hand| {
+                        previous
+                            .iter()
+                            .enumerate()
+                            .map(|(from, (prev, total, _))| {
+                                (total + cost(prev, &hand, rising), from)
+                            })
+                            .min()
+                            .map(|(total, from)| (hand, total, from))
+                    })
+                    .collect()
+            }
+        };
+        steps.push(step);
+    }
+
+    let last = steps.last()?;
+    let (mut index, _) = last
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, (_, total, _))| *total)?;
+    let mut stops = Vec::with_capacity(notes.len());
+    for step in steps.iter().rev() {
+        let (hand, _, from) = step[index];
+        stops.push(Stop {
+            finger: hand.finger,
+            string: hand.string,
+            position: hand.position,
+        });
+        index = from;
+    }
+    stops.reverse();
+    Some(stops)
+}
+
+#[cfg(test)]
+#[test]
+fn drills() {
+    let drill = |tonic: &str, scale, arpeggio, octaves| Drill {
+        tonic: tonic.parse().unwrap(),
+        scale,
+        arpeggio,
+        octaves,
+    };
+    let names = |drill: Drill| {
+        drill
+            .notes()
+            .unwrap()
+            .iter()
+            .map(|note| note.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        names(drill("A3", Scale::MelodicMinor, false, 1)),
+        "A3 B3 C4 D4 E4 F#4 G#4 A4 G4 F4 E4 D4 C4 B3 A3"
+    );
+    assert_eq!(
+        names(drill("Bb2", Scale::Major, true, 2)),
+        "Bb2 D3 F3 Bb3 D4 F4 Bb4 F4 D4 Bb3 F3 D3 Bb2"
+    );
+    assert_eq!(
+        drill("Eb3", Scale::Major, true, 2).name(),
+        "Eb major arpeggio, 2 octaves"
+    );
+
+    let cello: Vec<_> = Instrument::Cello.strings().collect();
+    let notes = drill("D3", Scale::Major, false, 2).notes().unwrap();
+    let stops = fingering(&notes, &cello).unwrap();
+    assert_eq!(stops.len(), notes.len());
+    for (note, stop) in notes.iter().zip(&stops) {
+        assert!(note.midi() > stop.string.open_note().midi());
+    }
+    // nothing below the lowest stopped note
+    assert_eq!(fingering(&["C2".parse().unwrap()], &cello), None);
+
+    // violin hands reach past 7th position, which has no name to give
+    let violin: Vec<_> = Instrument::Violin.strings().collect();
+    for (tonic, octaves) in [("A3", 3), ("A4", 2)] {
+        let notes = drill(tonic, Scale::Major, false, octaves).notes().unwrap();
+        let stops = fingering(&notes, &violin).unwrap();
+        assert_eq!(stops.len(), notes.len());
+        for (note, stop) in notes.iter().zip(&stops) {
+            assert!(note.midi() > stop.string.open_note().midi());
+            assert_ne!(stop.position, Position::Thumb);
+        }
+    }
+}
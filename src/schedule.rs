@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::cards::Card;
+
+/// Number of Leitner boxes. A card in box `n` comes back every `2^n` sessions.
+const BOXES: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    level: u8,
+    /// The first session the card should be shown in again.
+    due: u32,
+}
+
+/// Leitner-box spaced repetition over every card the student has been graded
+/// on, keyed by `Card::id`.
+///
+/// Each generated deck is a new session. A correct answer moves a card up a
+/// box and pushes it further into the future, a wrong answer sends it back to
+/// the first box, due in the very next session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    session: u32,
+    entries: HashMap<String, Entry>,
+}
+
+impl Schedule {
+    pub fn start_session(&mut self) {
+        self.session += 1;
+    }
+
+    pub fn grade(&mut self, card: &Card, correct: bool) {
+        let session = self.session;
+        let entry = self.entries.entry(card.id()).or_insert(Entry {
+            level: 0,
+            due: session,
+        });
+        if correct {
+            entry.level = (entry.level + 1).min(BOXES - 1);
+            entry.due = session + (1 << entry.level);
+        } else {
+            entry.level = 0;
+            entry.due = session + 1;
+        }
+    }
+
+    /// Puts due cards first, lowest box first so failed cards lead, then cards
+    /// never graded, then the rest by how soon they are due. Cards that tie keep
+    /// their order, so a shuffled deck stays shuffled within each group.
+    pub fn order(&self, cards: &mut [Card]) {
+        cards.sort_by_cached_key(|card| self.rank(card));
+    }
+
+    /// Where `order` puts a card: lower ranks come first.
+    pub fn rank(&self, card: &Card) -> (u8, u32) {
+        match self.entries.get(&card.id()) {
+            Some(entry) if entry.due <= self.session => (0, entry.level as u32),
+            None => (1, 0),
+            Some(entry) => (2, entry.due),
+        }
+    }
+}
+
+/// One `box due id` line per card after a `session` header, sorted by id so the
+/// text is stable.
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "session {}", self.session)?;
+        let mut ids: Vec<_> = self.entries.keys().collect();
+        ids.sort();
+        for id in ids {
+            let Entry { level, due } = self.entries[id];
+            writeln!(f, "{} {} {}", level, due, id)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let session = lines
+            .next()
+            .and_then(|line| line.strip_prefix("session "))
+            .and_then(|session| session.parse().ok())
+            .ok_or(())?;
+        let entries = lines
+            .map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let level = fields
+                    .next()
+                    .and_then(|level| level.parse().ok())
+                    .ok_or(())?;
+                let due = fields.next().and_then(|due| due.parse().ok()).ok_or(())?;
+                let id = fields.next().ok_or(())?;
+                if level >= BOXES {
+                    return Err(());
+                }
+                Ok((id.to_string(), Entry { level, due }))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Schedule { session, entries })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn leitner_schedule() {
+    let cards: Vec<Card> = ["D3:D3:E3:F3", "D3:E3:F3:G3", "D3:F3:G3:A3", "D3:G3:A3:B3"]
+        .map(|card| card.parse().unwrap())
+        .into();
+    let mut schedule = Schedule::default();
+    schedule.start_session();
+    schedule.grade(&cards[0], true);
+    schedule.grade(&cards[1], false);
+    schedule.grade(&cards[2], true);
+    schedule.grade(&cards[2], true);
+
+    schedule.start_session();
+    let mut deck = cards.clone();
+    schedule.order(&mut deck);
+    // failed, never seen, due in two sessions, due in four
+    assert_eq!(deck, [1, 3, 0, 2].map(|i| cards[i].clone()));
+
+    schedule.start_session();
+    schedule.order(&mut deck);
+    assert_eq!(deck, [1, 0, 3, 2].map(|i| cards[i].clone()));
+
+    let text = schedule.to_string();
+    assert_eq!(
+        text,
+        "session 3\n1 3 D3:D3:E3:F3\n0 2 D3:E3:F3:G3\n2 5 D3:F3:G3:A3\n"
+    );
+    assert_eq!(text.parse::<Schedule>(), Ok(schedule));
+}
@@ -0,0 +1,97 @@
+use std::fmt::{Display, Write};
+
+/// A minimal, DOM-free SVG document builder.
+///
+/// Elements are appended as markup in drawing order, so the same output can be
+/// handed to the browser (see `From<&Card> for Element`), written to a file or
+/// compared against a snapshot in a test.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    view_box: String,
+    body: String,
+}
+
+pub const LINE_STYLE: &str = "stroke: black; stroke-width: 1;";
+pub const FILL_STYLE: &str = "fill: #000000; fill-opacity: 1;";
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Self {
+        Svg {
+            view_box: format!("0 0 {} {}", width, height),
+            body: String::new(),
+        }
+    }
+
+    pub fn view_box(&self) -> &str {
+        &self.view_box
+    }
+
+    /// The child elements, without the enclosing `<svg>` tag.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn line(&mut self, x1: impl Display, y1: impl Display, x2: impl Display, y2: impl Display) {
+        writeln!(
+            self.body,
+            r#"<line x1="{}" x2="{}" y1="{}" y2="{}" style="{}"/>"#,
+            x1, x2, y1, y2, LINE_STYLE
+        )
+        .unwrap();
+    }
+
+    pub fn path(&mut self, d: &str) {
+        writeln!(self.body, r#"<path d="{}" style="{}"/>"#, d, FILL_STYLE).unwrap();
+    }
+
+    /// A filled glyph whose path data is drawn relative to (`x`, `y`).
+    pub fn glyph(&mut self, x: impl Display, y: impl Display, d: &str) {
+        writeln!(
+            self.body,
+            r#"<path transform="translate({},{})" d="{}" style="{}"/>"#,
+            x, y, d, FILL_STYLE
+        )
+        .unwrap();
+    }
+
+    /// An unfilled circle drawn in the line style.
+    pub fn circle(&mut self, cx: impl Display, cy: impl Display, r: impl Display) {
+        writeln!(
+            self.body,
+            r#"<circle cx="{}" cy="{}" r="{}" style="fill: none; {}"/>"#,
+            cx, cy, r, LINE_STYLE
+        )
+        .unwrap();
+    }
+
+    /// An unfilled path drawn in the line style.
+    pub fn outline(&mut self, d: &str) {
+        writeln!(
+            self.body,
+            r#"<path d="{}" style="fill: none; {}"/>"#,
+            d, LINE_STYLE
+        )
+        .unwrap();
+    }
+
+    pub fn text(&mut self, x: impl Display, y: impl Display, size: u32, content: &str) {
+        writeln!(
+            self.body,
+            r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" fill="black">{}</text>"#,
+            x, y, size, content
+        )
+        .unwrap();
+    }
+}
+
+impl Display for Svg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{}">"#,
+            self.view_box
+        )?;
+        f.write_str(&self.body)?;
+        f.write_str("</svg>")
+    }
+}
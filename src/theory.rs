@@ -0,0 +1,1257 @@
+//! Notes, clefs, key signatures and intervals, apart from how cards draw
+//! them. Every type reads and writes the same spelling the cards use, such as
+//! `F#3`, `2#` or `B4` for the treble clef.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+macro_rules! parse_error {
+    ($(#[$doc:meta])* $name:ident, $message:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name;
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str($message)
+            }
+        }
+
+        impl std::error::Error for $name {}
+    };
+}
+
+parse_error!(
+    /// Text that isn't one of the letters A to G.
+    ParseLetterError,
+    "expected a letter from A to G"
+);
+parse_error!(
+    /// Text that isn't one of `bbb`, `bb`, `b`, `n`, `#`, `##` or `###`.
+    ParseAccidentalError,
+    "expected bbb, bb, b, n, #, ## or ###"
+);
+parse_error!(
+    /// Text that isn't the middle line of a clef.
+    ParseClefError,
+    "expected the note on a clef's middle line, such as B4 for treble"
+);
+parse_error!(
+    /// Text that isn't an interval quality: `M`, `m`, `P`, `d`, `A`, `dd` or
+    /// `AA`.
+    ParseIntervalQualityError,
+    "expected M, m, P, d, A, dd or AA"
+);
+parse_error!(
+    /// Text that isn't a quality followed by a number, such as `m3` or `P-5`,
+    /// or names an interval that can't exist.
+    ParseIntervalError,
+    "expected an interval such as m3, A2 or P-5"
+);
+parse_error!(
+    /// Text that isn't up to seven sharps or flats.
+    ParseKeySignatureError,
+    "expected 0 or up to seven sharps or flats, such as 2# or 3b"
+);
+
+/// Why text couldn't be read as a [`Note`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseNoteError {
+    /// It doesn't start with a letter from A to G.
+    Letter,
+    /// The accidental isn't followed by a whole number octave.
+    Octave,
+    /// The note is below C-1 or above G9, so has no MIDI number.
+    OutOfRange,
+}
+
+impl fmt::Display for ParseNoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ParseNoteError::Letter => "expected a letter from A to G",
+            ParseNoteError::Octave => "expected an octave number, such as the 4 in C#4",
+            ParseNoteError::OutOfRange => "the note is outside the MIDI range of C-1 to G9",
+        })
+    }
+}
+
+impl std::error::Error for ParseNoteError {}
+
+/// A MIDI number above 127, which has no note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiOutOfRange(pub u8);
+
+impl fmt::Display for MidiOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MIDI number {} is above 127", self.0)
+    }
+}
+
+impl std::error::Error for MidiOutOfRange {}
+
+/// The letter a note is written with, numbered up from C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Letter {
+    C = 0,
+    D = 1,
+    E = 2,
+    F = 3,
+    G = 4,
+    A = 5,
+    B = 6,
+}
+
+impl From<Letter> for char {
+    #[inline]
+    fn from(letter: Letter) -> Self {
+        use Letter as L;
+        match letter {
+            L::A => 'A',
+            L::B => 'B',
+            L::C => 'C',
+            L::D => 'D',
+            L::E => 'E',
+            L::F => 'F',
+            L::G => 'G',
+        }
+    }
+}
+
+impl TryFrom<char> for Letter {
+    type Error = ParseLetterError;
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        use Letter as L;
+        Ok(match value {
+            'A' => L::A,
+            'B' => L::B,
+            'C' => L::C,
+            'D' => L::D,
+            'E' => L::E,
+            'F' => L::F,
+            'G' => L::G,
+            _ => {
+                return Err(ParseLetterError);
+            }
+        })
+    }
+}
+
+impl fmt::Display for Letter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl FromStr for Letter {
+    type Err = ParseLetterError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) => letter.try_into(),
+            _ => Err(ParseLetterError),
+        }
+    }
+}
+
+/// A clef, written as the note on its middle line: `B4` is treble, `B3`
+/// treble 8vb, `G4` soprano, `C4` alto, `A3` tenor, `F3` baritone and `D3` bass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Clef {
+    Treble,
+    TrebleOctaveDown,
+    Soprano,
+    Alto,
+    Tenor,
+    Baritone,
+    Bass,
+}
+
+impl FromStr for Clef {
+    type Err = ParseClefError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Clef as C;
+        Ok(match s {
+            "B4" => C::Treble,
+            "B3" => C::TrebleOctaveDown,
+            "G4" => C::Soprano,
+            "A3" => C::Tenor,
+            "C4" => C::Alto,
+            "F3" => C::Baritone,
+            "D3" => C::Bass,
+            _ => {
+                return Err(ParseClefError);
+            }
+        })
+    }
+}
+
+impl fmt::Display for Clef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.center_note().fmt(f)
+    }
+}
+
+impl Clef {
+    /// The note on the middle line of the staff.
+    pub const fn center_note(self) -> Note {
+        use Clef as C;
+        use Letter as L;
+        let accidental = None;
+        match self {
+            C::Treble => Note {
+                letter: L::B,
+                octave: 4,
+                accidental,
+            },
+            C::TrebleOctaveDown => Note {
+                letter: L::B,
+                octave: 3,
+                accidental,
+            },
+            C::Soprano => Note {
+                letter: L::G,
+                octave: 4,
+                accidental,
+            },
+            C::Alto => Note {
+                letter: L::C,
+                octave: 4,
+                accidental,
+            },
+            C::Tenor => Note {
+                letter: L::A,
+                octave: 3,
+                accidental,
+            },
+            C::Baritone => Note {
+                letter: L::F,
+                octave: 3,
+                accidental,
+            },
+            C::Bass => Note {
+                letter: L::D,
+                octave: 3,
+                accidental,
+            },
+        }
+    }
+}
+
+/// A key signature, counted in sharps (positive) or flats (negative).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct KeySignature {
+    pub(crate) fifths: i8,
+}
+
+impl FromStr for KeySignature {
+    type Err = ParseKeySignatureError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fifths = match s.as_bytes() {
+            [] | [b'0'] => 0,
+            [count @ b'1'..=b'7', b'#'] => (count - b'0') as i8,
+            [count @ b'1'..=b'7', b'b'] => -((count - b'0') as i8),
+            _ => {
+                return Err(ParseKeySignatureError);
+            }
+        };
+        Ok(KeySignature { fifths })
+    }
+}
+
+impl fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.fifths {
+            0 => f.write_str("0"),
+            fifths if fifths > 0 => write!(f, "{}#", fifths),
+            fifths => write!(f, "{}b", -fifths),
+        }
+    }
+}
+
+impl KeySignature {
+    const SHARP_ORDER: [Letter; 7] = {
+        use Letter::*;
+        [F, C, G, D, A, E, B]
+    };
+
+    /// The key with `fifths` sharps, or flats if negative, up to seven.
+    pub const fn new(fifths: i8) -> Option<KeySignature> {
+        if -7 <= fifths && fifths <= 7 {
+            Some(KeySignature { fifths })
+        } else {
+            None
+        }
+    }
+
+    /// The number of sharps, or of flats as a negative number.
+    pub const fn fifths(self) -> i8 {
+        self.fifths
+    }
+
+    /// How many semitones the key raises (or lowers) every note of `letter`.
+    pub fn alteration(self, letter: Letter) -> i8 {
+        let order = Self::SHARP_ORDER.iter().position(|&l| l == letter).unwrap() as i8;
+        if order < self.fifths {
+            1
+        } else if 6 - order < -self.fifths {
+            -1
+        } else {
+            0
+        }
+    }
+
+    /// Where each accidental of the signature sits on the staff, in the
+    /// order they are written.
+    pub fn placements(self, clef: Clef) -> impl Iterator<Item = (Note, Accidental)> {
+        use Clef as C;
+        let sharps = self.fifths > 0;
+        // octaves for each letter of the sharp (or flat) order
+        let octaves: [i8; 7] = match (clef, sharps) {
+            (C::Treble, true) => [5, 5, 5, 5, 4, 5, 4],
+            (C::Treble, false) => [4, 5, 4, 5, 4, 5, 4],
+            (C::TrebleOctaveDown, true) => [4, 4, 4, 4, 3, 4, 3],
+            (C::TrebleOctaveDown, false) => [3, 4, 3, 4, 3, 4, 3],
+            (C::Soprano, _) => [4, 5, 4, 5, 4, 5, 4],
+            (C::Alto, true) => [4, 4, 4, 4, 3, 4, 3],
+            (C::Alto, false) => [3, 4, 3, 4, 3, 4, 3],
+            (C::Tenor, true) => [3, 4, 3, 4, 3, 4, 3],
+            (C::Tenor, false) => [3, 4, 3, 4, 3, 4, 3],
+            (C::Baritone, true) => [3, 3, 3, 3, 2, 3, 2],
+            (C::Baritone, false) => [3, 3, 3, 3, 3, 3, 3],
+            (C::Bass, true) => [3, 3, 3, 3, 2, 3, 2],
+            (C::Bass, false) => [2, 3, 2, 3, 2, 3, 2],
+        };
+        let accidental = if sharps {
+            Accidental::Sharp
+        } else {
+            Accidental::Flat
+        };
+        let mut letters = Self::SHARP_ORDER;
+        if !sharps {
+            letters.reverse();
+        }
+        letters
+            .into_iter()
+            .zip(octaves)
+            .take(self.fifths.unsigned_abs() as usize)
+            .map(move |(letter, octave)| {
+                (
+                    Note {
+                        letter,
+                        octave,
+                        accidental: None,
+                    },
+                    accidental,
+                )
+            })
+    }
+}
+
+/// A sharp, flat, natural, double or triple, numbered by the semitones it adds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Accidental {
+    TripleFlat = -3,
+    DoubleFlat = -2,
+    Flat = -1,
+    Natural = 0,
+    Sharp = 1,
+    DoubleSharp = 2,
+    TripleSharp = 3,
+}
+
+impl TryFrom<i8> for Accidental {
+    type Error = i8;
+    #[inline]
+    fn try_from(val: i8) -> Result<Accidental, Self::Error> {
+        use Accidental as A;
+        Ok(match val {
+            -3 => A::TripleFlat,
+            -2 => A::DoubleFlat,
+            -1 => A::Flat,
+            0 => A::Natural,
+            1 => A::Sharp,
+            2 => A::DoubleSharp,
+            3 => A::TripleSharp,
+            _ => {
+                return Err(val);
+            }
+        })
+    }
+}
+
+impl fmt::Display for Accidental {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Accidental::Natural => "n",
+            accidental => accidental.to_str(),
+        })
+    }
+}
+
+impl FromStr for Accidental {
+    type Err = ParseAccidentalError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Accidental as A;
+        Ok(match s {
+            "bbb" => A::TripleFlat,
+            "bb" => A::DoubleFlat,
+            "b" => A::Flat,
+            "n" => A::Natural,
+            "#" => A::Sharp,
+            "##" => A::DoubleSharp,
+            "###" => A::TripleSharp,
+            _ => {
+                return Err(ParseAccidentalError);
+            }
+        })
+    }
+}
+
+impl Accidental {
+    /// How many semitones the accidental raises the note, or lowers it if
+    /// negative.
+    pub const fn semitones(self) -> i8 {
+        self as i8
+    }
+
+    /// Whether it is a triple sharp or flat, which most music never needs.
+    pub const fn is_triple(self) -> bool {
+        matches!(self, Accidental::TripleFlat | Accidental::TripleSharp)
+    }
+
+    /// How the accidental is spelled in a note name, where a natural is
+    /// left unwritten.
+    fn to_str(self) -> &'static str {
+        use Accidental as A;
+        match self {
+            A::TripleFlat => "bbb",
+            A::DoubleFlat => "bb",
+            A::Flat => "b",
+            A::Natural => "",
+            A::Sharp => "#",
+            A::DoubleSharp => "##",
+            A::TripleSharp => "###",
+        }
+    }
+}
+
+/// A written note: a letter, an optional accidental and an octave. Two
+/// spellings of the same pitch, such as `C#4` and `Db4`, are different notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Note {
+    pub(crate) letter: Letter,
+    /// Scientific pitch notation, so MIDI 0 is C-1 and 127 is G9.
+    pub(crate) octave: i8,
+    pub(crate) accidental: Option<Accidental>,
+}
+
+impl TryFrom<u8> for Note {
+    type Error = MidiOutOfRange;
+    fn try_from(midi: u8) -> Result<Note, Self::Error> {
+        Note::from_midi(midi).ok_or(MidiOutOfRange(midi))
+    }
+}
+
+impl FromStr for Note {
+    type Err = ParseNoteError;
+    fn from_str(src: &str) -> Result<Note, Self::Err> {
+        use Accidental as A;
+        let mut chars = src.chars();
+        let letter = chars
+            .next()
+            .and_then(|letter| Letter::try_from(letter).ok())
+            .ok_or(ParseNoteError::Letter)?;
+        let rest = chars.as_str();
+        let (accidental, octave) = [
+            ("###", A::TripleSharp),
+            ("##", A::DoubleSharp),
+            ("#", A::Sharp),
+            ("bbb", A::TripleFlat),
+            ("bb", A::DoubleFlat),
+            ("b", A::Flat),
+            ("n", A::Natural),
+        ]
+        .into_iter()
+        .find_map(|(spelling, accidental)| Some((Some(accidental), rest.strip_prefix(spelling)?)))
+        .unwrap_or((None, rest));
+        // only the octaves `Display` writes: no '+' and no leading zeros
+        let digits = octave.strip_prefix('-').unwrap_or(octave);
+        if digits.is_empty()
+            || !digits.bytes().all(|b| b.is_ascii_digit())
+            || (digits.starts_with('0') && octave != "0")
+        {
+            return Err(ParseNoteError::Octave);
+        }
+        let octave = octave.parse().map_err(|_| ParseNoteError::Octave)?;
+        Note::new(letter, accidental, octave).ok_or(ParseNoteError::OutOfRange)
+    }
+}
+
+/// Unlike `name`, an explicit natural is written, as "Cn4", so it survives
+/// being parsed back.
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", char::from(self.letter))?;
+        if let Some(accidental) = self.accidental {
+            write!(f, "{}", accidental)?;
+        }
+        write!(f, "{}", self.octave)
+    }
+}
+
+impl Ord for Note {
+    /// Lowest pitch first, and among spellings of one pitch, the lowest
+    /// letter first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.midi(), self.diatonic_step(), self.accidental).cmp(&(
+            other.midi(),
+            other.diatonic_step(),
+            other.accidental,
+        ))
+    }
+}
+
+impl PartialOrd for Note {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Note {
+    /// The note, or `None` if it has no MIDI number.
+    pub const fn new(letter: Letter, accidental: Option<Accidental>, octave: i8) -> Option<Note> {
+        let note = Note {
+            letter,
+            octave,
+            accidental,
+        };
+        match note.checked_midi() {
+            Some(_) => Some(note),
+            None => None,
+        }
+    }
+
+    /// The letter name, without the accidental.
+    pub const fn letter(self) -> Letter {
+        self.letter
+    }
+
+    /// The accidental written on the letter, or `None` if it has none.
+    pub const fn accidental(self) -> Option<Accidental> {
+        self.accidental
+    }
+
+    /// The octave number, going up at each C, so B#3 sounds the same as C4.
+    pub const fn octave(self) -> i8 {
+        self.octave
+    }
+
+    /// The letter and accidental, without the octave.
+    pub fn name(self) -> String {
+        let letter: char = self.letter.into();
+        format!("{}{}", letter, self.accidental.map_or("", |a| a.to_str()))
+    }
+
+    /// Semitones above the C below the note, from 0 to 11.
+    pub const fn pitch_class(self) -> u8 {
+        self.midi() % 12
+    }
+
+    /// Lines and spaces above C-1 the note is written on, whatever its
+    /// accidental.
+    pub const fn diatonic_step(self) -> i16 {
+        (self.octave as i16 + 1) * 7 + self.letter as i16
+    }
+
+    /// How far up the staff do you have to go to get to the other note?
+    pub const fn staff_distance(self, other: Note) -> i8 {
+        let octave_distance = (other.octave - self.octave) * 7;
+        let letter_distance = other.letter as i8 - self.letter as i8;
+        octave_distance + letter_distance
+    }
+
+    /// The MIDI number, where C4 is 60.
+    pub const fn midi(self) -> u8 {
+        match self.checked_midi() {
+            Some(midi) => midi,
+            None => panic!("notes are always in the MIDI range"),
+        }
+    }
+
+    /// The MIDI number, or `None` if the note is below C-1 or above G9.
+    pub const fn checked_midi(self) -> Option<u8> {
+        // note: c4 is 60
+        use Letter as L;
+        let midi = match self.letter {
+            L::C => 0i16,
+            L::D => 2,
+            L::E => 4,
+            L::F => 5,
+            L::G => 7,
+            L::A => 9,
+            L::B => 11,
+        } + if let Some(accidental) = self.accidental {
+            accidental as i16
+        } else {
+            0
+        } + 12 * (self.octave as i16 + 1);
+        if 0 <= midi && midi <= 127 {
+            Some(midi as u8)
+        } else {
+            None
+        }
+    }
+
+    /// The note for a MIDI number, spelled with sharps, or `None` above 127.
+    pub fn from_midi(midi: u8) -> Option<Note> {
+        if midi > 127 {
+            return None;
+        }
+        let octave = (midi / 12) as i8;
+        let after_octave = midi % 12;
+        let note_map = [0, 2, 4, 5, 7, 9, 11];
+        let reverse_note_map = [L::C, L::D, L::E, L::F, L::G, L::A, L::B];
+        let note = note_map.binary_search(&after_octave);
+        use Letter as L;
+        let (letter, accidental) = match note {
+            Ok(val) => (reverse_note_map[val], None),
+            Err(next_note) => (reverse_note_map[next_note - 1], {
+                assert!(
+                    after_octave - note_map[next_note - 1] == 1,
+                    "should have been found in the note map"
+                );
+                Some(Accidental::Sharp)
+            }),
+        };
+        Some(Note {
+            octave: octave - 1,
+            letter,
+            accidental,
+        })
+    }
+
+    /// The same pitch written with `letter`, if no more than a double sharp
+    /// or flat away.
+    pub fn enharmonic_equivalent(self, letter: Letter) -> Option<Note> {
+        self.enharmonic_equivalent_within(letter, Accidental::DoubleSharp)
+    }
+
+    /// The same pitch written with `letter`, if it needs no more sharps or
+    /// flats than `limit`, so callers can choose whether to reach for triples.
+    pub fn enharmonic_equivalent_within(self, letter: Letter, limit: Accidental) -> Option<Note> {
+        let letter_distance = letter as i8 - self.letter as i8;
+
+        let octave = match letter_distance {
+            5..=7 => self.octave.checked_sub(1)?,
+            -7..=-5 => self.octave.checked_add(1)?,
+            _ => self.octave,
+        };
+        let mut new_note = Note {
+            octave,
+            letter,
+            accidental: None,
+        };
+        let adjustment = new_note.checked_midi()? as i16 - self.midi() as i16;
+        if adjustment.abs() > limit.semitones().abs() as i16 {
+            //there is no enharmonic equivalent for this letter.
+            log::trace!("{self:?} has no enharmonic equivalent on {letter:?} ({adjustment} semitones needed)");
+            return None;
+        }
+        if adjustment != 0 {
+            new_note.accidental = Some(Accidental::try_from(-adjustment as i8).ok()?);
+        }
+        new_note.checked_midi().map(|_| new_note)
+    }
+
+    /// Every spelling of the same pitch, including this one, from C to B.
+    pub fn all_enharmonic_equivalents(self) -> impl Iterator<Item = Note> {
+        use Letter::*;
+        [C, D, E, F, G, A, B]
+            .into_iter()
+            .filter_map(move |letter| self.enharmonic_equivalent(letter))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn enharmonic_tests() {
+    assert_eq!(
+        "Cbb4"
+            .parse::<Note>()
+            .unwrap()
+            .enharmonic_equivalent(Letter::B)
+            .unwrap(),
+        "Bb3".parse().unwrap()
+    );
+}
+
+impl Add<Interval> for Note {
+    type Output = Option<Note>;
+    fn add(self, interval: Interval) -> Option<Note> {
+        let other_midi = u8::try_from(self.midi() as i16 + interval.midi_offset() as i16).ok()?;
+        let delta_letter = if interval.interval > 0 {
+            interval.interval - 1
+        } else {
+            interval.interval + 1
+        };
+        use Letter as L;
+        let new_letter = match (self.letter as i8 + delta_letter).rem_euclid(7) {
+            0 => L::C,
+            1 => L::D,
+            2 => L::E,
+            3 => L::F,
+            4 => L::G,
+            5 => L::A,
+            6 => L::B,
+            _ => unreachable!("_%7 is in the range 0..=6"),
+        };
+        Note::from_midi(other_midi)?
+            .enharmonic_equivalent_within(new_letter, Accidental::TripleSharp)
+    }
+}
+
+impl Sub for Note {
+    type Output = Option<Interval>;
+    /// The interval from `other` to this note, downwards if this note is
+    /// written lower, or `None` if it is more than doubly augmented or
+    /// diminished.
+    fn sub(self, other: Note) -> Option<Interval> {
+        if other.staff_distance(self) < 0 {
+            Interval::between(self, other).map(|interval| Interval {
+                interval: -interval.interval,
+                ..interval
+            })
+        } else {
+            Interval::between(other, self)
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn midi_notes() {
+    assert_eq!("C4".parse::<Note>().unwrap().midi(), 60);
+    assert_eq!("B##3".parse::<Note>().unwrap().midi(), 61);
+    assert_eq!("Abb4".parse::<Note>().unwrap().midi(), 67);
+}
+
+#[cfg(test)]
+#[test]
+fn midi_round_trips() {
+    assert_eq!("C-1".parse::<Note>().unwrap().midi(), 0);
+    assert_eq!("G9".parse::<Note>().unwrap().midi(), 127);
+    assert_eq!("Cb-1".parse::<Note>(), Err(ParseNoteError::OutOfRange));
+    assert_eq!("G#9".parse::<Note>(), Err(ParseNoteError::OutOfRange));
+    assert_eq!("C999".parse::<Note>(), Err(ParseNoteError::Octave));
+    assert_eq!(Note::try_from(128), Err(MidiOutOfRange(128)));
+    assert_eq!(Note::from_midi(0), "C-1".parse().ok());
+    for midi in 0..=127 {
+        let note = Note::try_from(midi).unwrap();
+        assert_eq!(note.midi(), midi);
+        assert!(note.all_enharmonic_equivalents().any(|n| n == note));
+        for spelling in note.all_enharmonic_equivalents() {
+            assert_eq!(spelling.midi(), midi);
+            assert_eq!(spelling.to_string().parse(), Ok(spelling));
+            assert_eq!(Note::from_midi(spelling.midi()), Some(note));
+        }
+    }
+    for midi in 128..=255 {
+        assert_eq!(Note::from_midi(midi), None);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn theory_strings() {
+    for text in [
+        "C4", "Cn4", "F#3", "Bbb2", "En5", "G##-1", "Cbbb5", "E###2", "A0", "Bn-1", "G9",
+    ] {
+        assert_eq!(text.parse::<Note>().unwrap().to_string(), text);
+    }
+    assert_ne!("Cn4".parse::<Note>(), "C4".parse::<Note>());
+    assert_eq!("H4".parse::<Note>(), Err(ParseNoteError::Letter));
+    for text in ["C#", "C4x", "C+4", "C04", "C-0", "C-", "Cn", "C 4", "C-04"] {
+        assert_eq!(
+            text.parse::<Note>(),
+            Err(ParseNoteError::Octave),
+            "{}",
+            text
+        );
+    }
+    for text in ["bbb", "bb", "b", "n", "#", "##", "###"] {
+        assert_eq!(text.parse::<Accidental>().unwrap().to_string(), text);
+    }
+    for text in ["A", "C", "G"] {
+        assert_eq!(text.parse::<Letter>().unwrap().to_string(), text);
+    }
+    assert_eq!("AB".parse::<Letter>(), Err(ParseLetterError));
+    for text in ["B4", "B3", "G4", "C4", "A3", "F3", "D3"] {
+        assert_eq!(text.parse::<Clef>().unwrap().to_string(), text);
+    }
+    assert_eq!("E4".parse::<Clef>(), Err(ParseClefError));
+    for text in ["0", "3#", "7b"] {
+        assert_eq!(text.parse::<KeySignature>().unwrap().to_string(), text);
+    }
+    assert_eq!("8#".parse::<KeySignature>(), Err(ParseKeySignatureError));
+
+    let mut notes: Vec<Note> = ["D4", "C#4", "Db4", "B#3", "C4"]
+        .iter()
+        .map(|n| n.parse().unwrap())
+        .collect();
+    notes.sort();
+    assert_eq!(
+        notes.iter().map(Note::to_string).collect::<Vec<_>>(),
+        ["B#3", "C4", "C#4", "Db4", "D4"]
+    );
+    let note: Note = "Db4".parse().unwrap();
+    assert_eq!((note.pitch_class(), note.diatonic_step()), (1, 36));
+    assert_eq!("B#3".parse::<Note>().unwrap().pitch_class(), 0);
+}
+
+/// Whether an interval is major, minor, perfect, or altered from one of those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntervalQuality {
+    Major,
+    Perfect,
+    Minor,
+    Diminished,
+    Augmented,
+    DoublyDiminished,
+    DoublyAugmented,
+}
+
+impl fmt::Display for IntervalQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IntervalQuality as Q;
+        f.write_str(match self {
+            Q::Major => "M",
+            Q::Perfect => "P",
+            Q::Minor => "m",
+            Q::Diminished => "d",
+            Q::Augmented => "A",
+            Q::DoublyDiminished => "dd",
+            Q::DoublyAugmented => "AA",
+        })
+    }
+}
+
+impl FromStr for IntervalQuality {
+    type Err = ParseIntervalQualityError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use IntervalQuality as Q;
+        Ok(match s {
+            "M" => Q::Major,
+            "P" => Q::Perfect,
+            "m" => Q::Minor,
+            "d" => Q::Diminished,
+            "A" => Q::Augmented,
+            "dd" => Q::DoublyDiminished,
+            "AA" => Q::DoublyAugmented,
+            _ => {
+                return Err(ParseIntervalQualityError);
+            }
+        })
+    }
+}
+
+impl IntervalQuality {
+    /// The quality of the interval turned upside down: major and minor swap,
+    /// as do augmented and diminished.
+    pub const fn inverted(self) -> IntervalQuality {
+        use IntervalQuality as Q;
+        match self {
+            Q::Major => Q::Minor,
+            Q::Perfect => Q::Perfect,
+            Q::Minor => Q::Major,
+            Q::Diminished => Q::Augmented,
+            Q::Augmented => Q::Diminished,
+            Q::DoublyDiminished => Q::DoublyAugmented,
+            Q::DoublyAugmented => Q::DoublyDiminished,
+        }
+    }
+}
+
+/// Why an interval can't be made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalError {
+    /// Intervals count from 1 for a unison, so there is no 0th.
+    Zero,
+    /// Unisons, 4ths, 5ths and octaves are perfect rather than major or
+    /// minor, and the other numbers are never perfect.
+    Quality(IntervalQuality, i8),
+    /// The interval spans more semitones than MIDI has notes.
+    TooWide(IntervalQuality, i8),
+}
+
+impl fmt::Display for IntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntervalError::Zero => f.write_str("intervals are numbered from 1"),
+            IntervalError::Quality(IntervalQuality::Perfect, interval) => {
+                write!(f, "{}'s cannot be perfect", interval)
+            }
+            IntervalError::Quality(quality, interval) => write!(
+                f,
+                "{}'s cannot be {}",
+                interval,
+                Interval::quality_name(*quality)
+            ),
+            IntervalError::TooWide(quality, interval) => {
+                write!(
+                    f,
+                    "{} {}'s span more than 127 semitones",
+                    Interval::quality_name(*quality),
+                    interval
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntervalError {}
+
+/// An interval by quality and number, counted from 1 for a unison. A
+/// negative number is the same interval downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub(crate) quality: IntervalQuality,
+    pub(crate) interval: i8,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.quality, self.interval)
+    }
+}
+
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (quality, number) = s.split_at(
+            s.find(|c: char| c.is_ascii_digit() || c == '-')
+                .ok_or(ParseIntervalError)?,
+        );
+        let quality = quality.parse().map_err(|_| ParseIntervalError)?;
+        let interval = number.parse().map_err(|_| ParseIntervalError)?;
+        Interval::new(quality, interval).map_err(|_| ParseIntervalError)
+    }
+}
+
+impl Interval {
+    /// Fails on a 0th, a quality the number can't have, or an interval too
+    /// wide for MIDI (`IntervalError::TooWide`).
+    pub fn new(quality: IntervalQuality, interval: i8) -> Result<Interval, IntervalError> {
+        use IntervalQuality as Q;
+        if interval == 0 {
+            return Err(IntervalError::Zero);
+        }
+        match ((interval.unsigned_abs() - 1) % 7, quality) {
+            (0 | 3 | 4, Q::Major | Q::Minor) | (1 | 2 | 5 | 6, Q::Perfect) => {
+                Err(IntervalError::Quality(quality, interval))
+            }
+            _ if Interval { quality, interval }.semitones().abs() > i8::MAX as i16 => {
+                Err(IntervalError::TooWide(quality, interval))
+            }
+            _ => Ok(Interval { quality, interval }),
+        }
+    }
+
+    /// Major, minor, perfect and so on.
+    pub const fn quality(self) -> IntervalQuality {
+        self.quality
+    }
+
+    /// The number, from 1 for a unison, negative for a downward interval.
+    pub const fn number(self) -> i8 {
+        self.interval
+    }
+
+    /// Whether the interval is wider than an octave.
+    pub const fn is_compound(self) -> bool {
+        self.interval.unsigned_abs() > 8
+    }
+
+    /// The interval with whole octaves taken off until it fits in one, as a
+    /// 10th reduces to a 3rd. Unisons and octaves stay as they are.
+    pub const fn simple(self) -> Interval {
+        let number = self.interval.unsigned_abs();
+        let simple = if number <= 8 {
+            number
+        } else {
+            (number - 2) % 7 + 2
+        } as i8;
+        Interval {
+            quality: self.quality,
+            interval: if self.interval < 0 { -simple } else { simple },
+        }
+    }
+
+    /// The simple interval that makes an octave with this one, in the same
+    /// direction: a major 3rd inverts to a minor 6th.
+    pub const fn inverted(self) -> Interval {
+        let simple = self.simple();
+        let number = 9 - simple.interval.abs();
+        Interval {
+            quality: self.quality.inverted(),
+            interval: if self.interval < 0 { -number } else { number },
+        }
+    }
+
+    /// The interval from `low` up to `high`, or `None` if `high` is below
+    /// `low` or the two are too far out of tune to be a named interval.
+    pub fn between(low: Note, high: Note) -> Option<Interval> {
+        use IntervalQuality as Q;
+        let steps = low.staff_distance(high);
+        if steps < 0 {
+            return None;
+        }
+        let major_perfect_offsets = [0i8, 2, 4, 5, 7, 9, 11];
+        let expected = (steps / 7) as i16 * 12 + major_perfect_offsets[(steps % 7) as usize] as i16;
+        let semitones = high.midi() as i16 - low.midi() as i16;
+        let quality = match (steps % 7, semitones - expected) {
+            (0 | 3 | 4, -2) => Q::DoublyDiminished,
+            (0 | 3 | 4, -1) => Q::Diminished,
+            (0 | 3 | 4, 0) => Q::Perfect,
+            (0 | 3 | 4, 1) => Q::Augmented,
+            (0 | 3 | 4, 2) => Q::DoublyAugmented,
+            (0 | 3 | 4, _) => return None,
+            (_, -3) => Q::DoublyDiminished,
+            (_, -2) => Q::Diminished,
+            (_, -1) => Q::Minor,
+            (_, 0) => Q::Major,
+            (_, 1) => Q::Augmented,
+            (_, 2) => Q::DoublyAugmented,
+            _ => return None,
+        };
+        Some(Interval {
+            quality,
+            interval: steps + 1,
+        })
+    }
+
+    /// The interval spelled out, e.g. "minor 3rd" or "perfect octave".
+    pub fn name(self) -> String {
+        let quality = Interval::quality_name(self.quality);
+        let number = match self.interval.unsigned_abs() {
+            1 => "unison".to_string(),
+            8 => "octave".to_string(),
+            n => match (n % 10, n % 100) {
+                (_, 11..=13) => format!("{}th", n),
+                (1, _) => format!("{}st", n),
+                (2, _) => format!("{}nd", n),
+                (3, _) => format!("{}rd", n),
+                _ => format!("{}th", n),
+            },
+        };
+        format!("{} {}", quality, number)
+    }
+
+    fn quality_name(quality: IntervalQuality) -> &'static str {
+        use IntervalQuality as Q;
+        match quality {
+            Q::Major => "major",
+            Q::Perfect => "perfect",
+            Q::Minor => "minor",
+            Q::Diminished => "diminished",
+            Q::Augmented => "augmented",
+            Q::DoublyDiminished => "doubly diminished",
+            Q::DoublyAugmented => "doubly augmented",
+        }
+    }
+
+    /// The semitones spanned, negative for a downward interval.
+    pub fn midi_offset(self) -> i8 {
+        // `new` turns away anything wider than this can hold
+        self.semitones() as i8
+    }
+
+    fn semitones(self) -> i16 {
+        use IntervalQuality as Q;
+        let interval: u8 = self.interval.unsigned_abs() - 1;
+        let octaves = (interval / 7) as i16;
+        let interval_index = (interval % 7) as usize;
+        let major_perfect_offsets = [0i16, 2, 4, 5, 7, 9, 11];
+        log::trace!("{self:?} spans {octaves} octaves and {interval_index} steps");
+        let offset = octaves * 12
+            + match self.quality {
+                Q::Major | Q::Perfect => major_perfect_offsets[interval_index],
+                Q::Diminished => match interval_index {
+                    0 | 3 | 4 => major_perfect_offsets[interval_index] - 1,
+                    _ => major_perfect_offsets[interval_index] - 2,
+                },
+                Q::DoublyDiminished => match interval_index {
+                    0 | 3 | 4 => major_perfect_offsets[interval_index] - 2,
+                    _ => major_perfect_offsets[interval_index] - 3,
+                },
+                Q::Minor => major_perfect_offsets[interval_index] - 1,
+                Q::Augmented => major_perfect_offsets[interval_index] + 1,
+                Q::DoublyAugmented => major_perfect_offsets[interval_index] + 2,
+            };
+        if self.interval > 0 {
+            offset
+        } else {
+            -offset
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn interval_tests() {
+    use Accidental::*;
+    use Letter::*;
+    let test_interval = Interval {
+        interval: 1,
+        quality: IntervalQuality::Perfect,
+    };
+    for letter in [A, B, C, D, E, F, G] {
+        for octave in 0..=8 {
+            let note = Note {
+                letter,
+                octave,
+                accidental: None,
+            };
+            println!("{note:?}");
+            assert_eq!(note.midi(), (note + test_interval).unwrap().midi());
+            for accidental in [DoubleFlat, DoubleSharp, Flat, Sharp, Natural] {
+                let note = Note {
+                    letter,
+                    octave,
+                    accidental: Some(accidental),
+                };
+                println!("{note:?}");
+                assert_eq!(note.midi(), (note + test_interval).unwrap().midi());
+            }
+        }
+    }
+    assert_eq!(
+        Interval::new(IntervalQuality::Perfect, 8)
+            .unwrap()
+            .midi_offset(),
+        12
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Major, 10)
+            .unwrap()
+            .midi_offset(),
+        16
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Diminished, 7)
+            .unwrap()
+            .midi_offset(),
+        9
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Perfect, 1)
+            .unwrap()
+            .midi_offset(),
+        0
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Major, -3)
+            .unwrap()
+            .midi_offset(),
+        -4
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Major, 4),
+        Err(IntervalError::Quality(IntervalQuality::Major, 4))
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Perfect, -6),
+        Err(IntervalError::Quality(IntervalQuality::Perfect, -6))
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Minor, 0),
+        Err(IntervalError::Zero)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn interval_arithmetic() {
+    let note = |name: &str| name.parse::<Note>().unwrap();
+    let interval = |name: &str| name.parse::<Interval>().unwrap();
+    assert_eq!(note("F#4") - note("Bb3"), Some(interval("A5")));
+    assert_eq!(note("Bb3") - note("F#4"), Some(interval("A-5")));
+    assert_eq!(note("E5") - note("C4"), Some(interval("M10")));
+    assert_eq!(note("C4") - note("C4"), Some(interval("P1")));
+    assert_eq!(note("C##4") - note("C4"), Some(interval("AA1")));
+    assert_eq!(note("C##4") - note("Cb4"), None);
+    assert_eq!(note("F4") - note("B#3"), Some(interval("dd5")));
+    assert_eq!(note("C#4") + interval("AA4"), Some(note("F###4")));
+    assert_eq!(note("Fb4") + interval("m-2"), Some(note("Eb4")));
+    assert_eq!(note("Fbb4") + interval("P4"), Some(note("Bbbb4")));
+    assert_eq!(note("F##4").enharmonic_equivalent(Letter::E), None);
+    assert_eq!(
+        note("F##4").enharmonic_equivalent_within(Letter::E, Accidental::TripleSharp),
+        Some(note("E###4"))
+    );
+
+    for text in ["m3", "A2", "P-5", "d7", "M10", "P15", "AA4", "dd-3"] {
+        assert_eq!(interval(text).to_string(), text);
+    }
+    for text in ["", "m", "X3", "é3", "P3", "M5", "m0", "P1.5"] {
+        assert_eq!(text.parse::<Interval>(), Err(ParseIntervalError));
+    }
+
+    assert_eq!(interval("M3").inverted(), interval("m6"));
+    assert_eq!(interval("A4").inverted(), interval("d5"));
+    assert_eq!(interval("dd3").inverted(), interval("AA6"));
+    assert_eq!(interval("P8").inverted(), interval("P1"));
+    assert_eq!(interval("m-2").inverted(), interval("M-7"));
+    assert_eq!(interval("M10").inverted(), interval("m6"));
+    assert_eq!(interval("M10").simple(), interval("M3"));
+    assert_eq!(interval("P15").simple(), interval("P8"));
+    assert_eq!(interval("P8").simple(), interval("P8"));
+    assert!(interval("M9").is_compound() && !interval("P8").is_compound());
+    for (text, name) in [
+        ("m2", "minor 2nd"),
+        ("P11", "perfect 11th"),
+        ("M13", "major 13th"),
+        ("P22", "perfect 22nd"),
+        ("M31", "major 31st"),
+        ("P-32", "perfect 32nd"),
+        ("M73", "major 73rd"),
+    ] {
+        assert_eq!(interval(text).name(), name);
+    }
+
+    // the widest intervals still fit in MIDI's 127 semitones
+    assert_eq!(interval("P75").midi_offset(), 127);
+    assert_eq!(interval("d-76").midi_offset(), -127);
+    assert_eq!(
+        Interval::new(IntervalQuality::Major, 76),
+        Err(IntervalError::TooWide(IntervalQuality::Major, 76))
+    );
+    assert_eq!(
+        Interval::new(IntervalQuality::Augmented, -75),
+        Err(IntervalError::TooWide(IntervalQuality::Augmented, -75))
+    );
+    assert_eq!("M77".parse::<Interval>(), Err(ParseIntervalError));
+    assert_eq!("P127".parse::<Interval>(), Err(ParseIntervalError));
+    assert_eq!(
+        Note::from_midi(0).unwrap() + interval("P75"),
+        Note::from_midi(127)
+    );
+
+    // going back by the difference lands on the same spelling, in every direction
+    let notes: Vec<Note> = (48..=72)
+        .filter_map(Note::from_midi)
+        .flat_map(Note::all_enharmonic_equivalents)
+        .collect();
+    for &low in &notes {
+        for &high in &notes {
+            if let Some(interval) = high - low {
+                assert_eq!(low + interval, Some(high), "{} + {}", low, interval);
+                assert_eq!(
+                    interval.midi_offset() as i16,
+                    high.midi() as i16 - low.midi() as i16
+                );
+            }
+        }
+    }
+}